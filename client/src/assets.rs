@@ -0,0 +1,212 @@
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use image::DynamicImage;
+use shared::{
+    resources::{self, ResourceError, Vfs},
+    toml,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AssetKind {
+    /// Decoded into RGBA pixels ready for upload
+    Image,
+    Text,
+    /// Parsed as a TOML table
+    Definition,
+}
+
+/// A file to load from its logical `namespace:path`, `key` is the `(namespace, name)` pair it is handed back under
+#[derive(Clone, Debug)]
+pub struct AssetRequest {
+    pub key: (String, String),
+    pub path: String,
+    pub kind: AssetKind,
+}
+
+impl AssetRequest {
+    pub fn new(key: (String, String), path: impl Into<String>, kind: AssetKind) -> Self {
+        Self {
+            key,
+            path: path.into(),
+            kind,
+        }
+    }
+}
+
+pub enum AssetData {
+    Image(DynamicImage),
+    Text(String),
+    Definition(toml::Table),
+}
+
+pub struct Asset {
+    pub key: (String, String),
+    pub data: AssetData,
+}
+
+/// How much of a batch of assets has been loaded, for loading screens
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progress {
+    pub items_done: usize,
+    pub items_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+impl Progress {
+    /// Between 0 and 1, weighted by file size when sizes are known
+    pub fn fraction(&self) -> f32 {
+        if self.bytes_total > 0 {
+            (self.bytes_done as f64 / self.bytes_total as f64).min(1.0) as f32
+        } else if self.items_total > 0 {
+            self.items_done as f32 / self.items_total as f32
+        } else {
+            1.0
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.items_done >= self.items_total
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    items_done: AtomicUsize,
+    bytes_done: AtomicU64,
+}
+
+/// Reads, decodes and parses assets from a `Vfs` on worker threads
+///
+/// Finished assets queue up until the render thread collects them with `next_batch`, so GPU uploads can be spread
+/// over several frames
+pub struct AssetLoader {
+    receiver: Receiver<resources::Result<Asset>>,
+    counters: Arc<Counters>,
+    items_total: usize,
+    bytes_total: u64,
+    received: usize,
+}
+
+impl AssetLoader {
+    #[profiling::function]
+    pub fn new(vfs: Arc<Vfs>, requests: Vec<AssetRequest>) -> Self {
+        let items_total = requests.len();
+        let requests: VecDeque<_> = requests
+            .into_iter()
+            .map(|request| {
+                let size = vfs.file_size(&request.path).unwrap_or(0);
+                (request, size)
+            })
+            .collect();
+        let bytes_total = requests.iter().map(|(_, size)| size).sum();
+
+        let threads = thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1)
+            .min(items_total);
+
+        let queue = Arc::new(Mutex::new(requests));
+        let counters = Arc::new(Counters::default());
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..threads {
+            let vfs = vfs.clone();
+            let queue = queue.clone();
+            let counters = counters.clone();
+            let sender = sender.clone();
+            thread::spawn(move || {
+                profiling::register_thread!("Asset Loader");
+                work(&vfs, &queue, &counters, &sender);
+            });
+        }
+
+        Self {
+            receiver,
+            counters,
+            items_total,
+            bytes_total,
+            received: 0,
+        }
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            items_done: self.counters.items_done.load(Ordering::Relaxed),
+            items_total: self.items_total,
+            bytes_done: self.counters.bytes_done.load(Ordering::Relaxed),
+            bytes_total: self.bytes_total,
+        }
+    }
+
+    /// Takes up to `max` finished assets without blocking
+    #[profiling::function]
+    pub fn next_batch(&mut self, max: usize) -> Vec<resources::Result<Asset>> {
+        let batch: Vec<_> = self.receiver.try_iter().take(max).collect();
+        self.received += batch.len();
+        batch
+    }
+
+    /// Whether every asset has been handed out by `next_batch`
+    pub fn is_finished(&self) -> bool {
+        self.received >= self.items_total
+    }
+}
+
+fn work(
+    vfs: &Vfs,
+    queue: &Mutex<VecDeque<(AssetRequest, u64)>>,
+    counters: &Counters,
+    sender: &Sender<resources::Result<Asset>>,
+) {
+    loop {
+        let request = queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop_front();
+        let Some((request, size)) = request else { return };
+
+        let asset = load(vfs, request);
+        // The expected size counts whether or not the item loaded, so progress still reaches the end
+        counters.bytes_done.fetch_add(size, Ordering::Relaxed);
+        counters.items_done.fetch_add(1, Ordering::Relaxed);
+        if sender.send(asset).is_err() {
+            // The loader was dropped, nobody wants the rest
+            return;
+        }
+    }
+}
+
+#[profiling::function]
+fn load(vfs: &Vfs, request: AssetRequest) -> resources::Result<Asset> {
+    let path = Path::new(&request.path);
+    let bytes = vfs.read_bytes(&request.path)?;
+
+    let data = match request.kind {
+        AssetKind::Image => {
+            let image = image::load_from_memory(&bytes).map_err(|e| ResourceError::parse(path, e))?;
+            AssetData::Image(DynamicImage::ImageRgba8(image.to_rgba8()))
+        }
+        AssetKind::Text => AssetData::Text(
+            String::from_utf8(bytes).map_err(|e| ResourceError::parse(path, e))?,
+        ),
+        AssetKind::Definition => {
+            let text = String::from_utf8(bytes).map_err(|e| ResourceError::parse(path, e))?;
+            AssetData::Definition(toml::from_str(&text).map_err(|e| ResourceError::parse(path, e.message()))?)
+        }
+    };
+
+    Ok(Asset {
+        key: request.key,
+        data,
+    })
+}
@@ -1,13 +1,19 @@
+use std::path::PathBuf;
+
 pub use wgpu::PolygonMode;
 
 pub struct Config {
     pub debug: Debug,
     pub meshing_distance: MeshingDistance,
     pub gamma: Gamma,
+    pub hot_reload: HotReload,
 }
 
 pub struct Debug(pub PolygonMode);
 
 pub struct MeshingDistance(pub u16);
 
-pub struct Gamma(pub f32);
\ No newline at end of file
+pub struct Gamma(pub f32);
+
+/// Files and directories watched for changed shaders, textures and addon definitions, empty disables hot reloading
+pub struct HotReload(pub Vec<PathBuf>);
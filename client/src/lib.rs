@@ -1,12 +1,11 @@
-// TODO: Use bbmodel format directly
-
 use std::hash::Hash;
-use std::{sync::mpsc::Receiver, thread};
+use std::sync::{mpsc::Receiver, Arc};
+use std::thread;
 
 use config::Config;
 use input::{Input, InputInfo};
 use shared::types::{block, item};
-use shared::{registry::Registry, Module, StaticModule};
+use shared::{registry::Registry, resources::Vfs, Module, StaticModule};
 use std::fmt::Debug;
 use window::texture::Texture;
 use window::Window;
@@ -16,6 +15,7 @@ pub mod config;
 pub mod input;
 pub mod types;
 
+pub mod assets;
 pub mod interface;
 pub mod mesher;
 pub mod network;
@@ -24,7 +24,7 @@ pub mod window;
 mod world;
 
 pub struct Client<
-    T: Debug + Eq + Hash + PartialEq,
+    T: Clone + Debug + Eq + Hash + PartialEq,
     B: block::Block,
     I: item::Item,
     D,
@@ -41,7 +41,7 @@ pub struct ClientIO {
 //#[profiling::function]
 pub fn init<
     State: 'static,
-    CustomType: Debug + Eq + Hash + PartialEq + 'static,
+    CustomType: Clone + Debug + Eq + Hash + PartialEq + 'static,
     Block: block::Block + 'static,
     Item: item::Item + 'static,
     CustomData: 'static,
@@ -97,11 +97,15 @@ pub fn init<
     // TODO: lua vms inside addon manager on separate thread
 
     let (_, window) = Window::new(());
-    window.run::<State, CustomType, Block, Item, CustomData, F, E, R>((state, client, client_io, frame, exit, resources));
+    window.run::<State, CustomType, Block, Item, CustomData, F, E, R>((state, client, client_io, frame, exit, reload, resources));
 }
 
 pub struct Resources {
     pub world_shader: String,
     pub skybox_shader: String,
     pub textures: Vec<((String, String), Texture)>,
+    /// Logical paths of texture files decoded on worker threads and uploaded in batches, applied once all of them are loaded
+    pub texture_files: Vec<((String, String), String)>,
+    /// Where `texture_files` are read from
+    pub vfs: Arc<Vfs>,
 }
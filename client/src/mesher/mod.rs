@@ -1,6 +1,7 @@
 use shared::Module;
 
 pub mod chunk;
+pub mod model;
 
 struct Mesher {
     //
@@ -0,0 +1,37 @@
+use shared::direction::Direction;
+use shared::math::{UVec4, Vec3};
+use shared::model::BakedModel;
+
+use crate::window::surface::vertex::Vertex;
+
+/// Appends the quads of `model` placed at `position`, skipping quads whose cull face is hidden
+///
+/// `textures` maps the model's texture slots to texture array indices
+#[profiling::function]
+pub fn emit_model(
+    model: &BakedModel,
+    position: Vec3,
+    textures: &[u32],
+    hidden: impl Fn(Direction) -> bool,
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u32>,
+) {
+    for quad in &model.quads {
+        if quad.cull_face.is_some_and(&hidden) {
+            continue;
+        }
+
+        let texture_index = textures.get(quad.texture).copied().unwrap_or(0);
+        let start = vertices.len() as u32;
+        indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+
+        for (corner, uv) in quad.positions.iter().zip(quad.uvs) {
+            vertices.push(Vertex {
+                position: (*corner + position).to_array(),
+                normal: quad.normal.to_array(),
+                texture_index,
+                data: Vertex::encode_uv(UVec4::new(0, 0, 0, 15), uv),
+            });
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use std::hash::Hash;
-use std::time::Instant;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use pollster::block_on;
 use shared::types::{block::Block, item::Item};
-use shared::{log::warn, util::ThisOrThat, StaticModule};
+use shared::{log::{error, info, warn}, resources::{Vfs, Watcher}, util::ThisOrThat, StaticModule};
 use std::fmt::Debug;
 use winit::{
     event::{ElementState, Event, WindowEvent},
@@ -12,16 +14,26 @@ use winit::{
 };
 
 use crate::{
+    assets::{Asset, AssetData, AssetKind, AssetLoader, AssetRequest, Progress},
     input::{Input, InputInfo, Key},
     Client, ClientIO, Resources,
 };
 
-use self::surface::WindowSurface;
+use self::{surface::WindowSurface, texture::Texture};
 
 pub mod camera;
 pub mod surface;
 pub mod texture;
 
+/// How often the hot reload watcher checks for changed files
+const RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Shaders, textures and addon definitions
+const RELOAD_EXTENSIONS: &[&str] = &["wgsl", "png", "toml", "json"];
+
+/// Textures uploaded to the GPU per frame while loading
+const UPLOAD_BATCH: usize = 16;
+
 pub struct Window {
     pub info: WindowInfo,
 }
@@ -30,6 +42,8 @@ pub struct Window {
 pub struct WindowInfo {
     pub delta: f64,
     pub fps: f64,
+    /// Set while textures are loading in the background
+    pub loading: Option<Progress>,
 }
 
 impl StaticModule<(), ()> for Window {
@@ -48,7 +62,7 @@ impl Window {
     #[profiling::function]
     pub fn run<
         S: 'static,
-        T: Debug + Eq + Hash + PartialEq + 'static,
+        T: Clone + Debug + Eq + Hash + PartialEq + 'static,
         B: Block + 'static,
         I: Item + 'static,
         D: 'static,
@@ -57,12 +71,13 @@ impl Window {
         R: Fn(&mut S, &mut Client<T, B, I, D>, &ClientIO) -> Resources + 'static,
     >(
         mut self,
-        (mut game_state, mut client, client_io, frame, exit, resources): (
+        (mut game_state, mut client, client_io, frame, exit, reload, mut resources): (
             S,
             Client<T, B, I, D>,
             ClientIO,
             F,
             E,
+            R,
             Resources,
         ),
     ) where
@@ -72,9 +87,15 @@ impl Window {
         let event_loop = EventLoop::new();
         let window = WindowBuilder::new().build(&event_loop).unwrap();
 
+        let mut pending = PendingResources::start(&mut resources);
         let mut state = block_on(WindowSurface::new(window, &client.config, resources));
         let mut last_render_time = Instant::now();
 
+        let mut watcher = Watcher::new(RELOAD_INTERVAL).with_extensions(RELOAD_EXTENSIONS);
+        for path in &client.config.hot_reload.0 {
+            watcher.watch(path);
+        }
+
         event_loop.run(move |event, _, control_flow| match event {
             Event::WindowEvent {
                 ref event,
@@ -138,9 +159,92 @@ impl Window {
                 profiling::finish_frame!();
             }
 
-            Event::MainEventsCleared => state.window().request_redraw(),
+            Event::MainEventsCleared => {
+                let changed = watcher.poll();
+                if !changed.is_empty() {
+                    info!("Reloading resources, {} files changed", changed.len());
+                    let mut resources = reload(&mut game_state, &mut client, &client_io);
+                    pending = PendingResources::start(&mut resources);
+                    if pending.is_none() {
+                        state.reload(&client.config, resources);
+                    }
+                }
+
+                if let Some(loading) = &mut pending {
+                    loading.upload(&state);
+                    self.info.loading = Some(loading.loader.progress());
+                    if loading.loader.is_finished() {
+                        if let Some(loading) = pending.take() {
+                            state.reload(&client.config, loading.into_resources());
+                        }
+                        self.info.loading = None;
+                    }
+                }
+
+                state.window().request_redraw()
+            }
 
             _ => {}
         });
     }
 }
+
+/// Resources whose texture files are still loading, the shaders are applied together with the finished textures
+struct PendingResources {
+    loader: AssetLoader,
+    world_shader: String,
+    skybox_shader: String,
+    textures: Vec<((String, String), Texture)>,
+    vfs: Arc<Vfs>,
+}
+
+impl PendingResources {
+    /// Starts loading the texture files of `resources` in the background, `None` if there are none
+    fn start(resources: &mut Resources) -> Option<Self> {
+        if resources.texture_files.is_empty() {
+            return None;
+        }
+
+        let requests = mem::take(&mut resources.texture_files)
+            .into_iter()
+            .map(|(key, path)| AssetRequest::new(key, path, AssetKind::Image))
+            .collect();
+
+        Some(Self {
+            loader: AssetLoader::new(resources.vfs.clone(), requests),
+            world_shader: resources.world_shader.clone(),
+            skybox_shader: resources.skybox_shader.clone(),
+            textures: mem::take(&mut resources.textures),
+            vfs: resources.vfs.clone(),
+        })
+    }
+
+    #[profiling::function]
+    fn upload(&mut self, state: &WindowSurface) {
+        for asset in self.loader.next_batch(UPLOAD_BATCH) {
+            match asset {
+                Ok(Asset { key, data: AssetData::Image(image) }) => {
+                    let label = format!("{}:{}", key.0, key.1);
+                    match state.create_texture(&image, &label) {
+                        Ok(texture) => self.textures.push((key, texture)),
+                        Err(e) => error!("Failed to upload texture {label}: {e}"),
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to load texture {e}"),
+            }
+        }
+    }
+
+    /// Textures are sorted by key, so their order doesn't depend on which worker finished first
+    fn into_resources(mut self) -> Resources {
+        self.textures.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Resources {
+            world_shader: self.world_shader,
+            skybox_shader: self.skybox_shader,
+            textures: self.textures,
+            texture_files: vec![],
+            vfs: self.vfs,
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use std::{collections::HashMap, iter, num::NonZeroU32, time::Instant};
+use std::{iter, num::NonZeroU32, time::Instant};
 
+use pollster::block_on;
 use shared::{
+    log::error,
     math::{Mat4, Vec3},
-    util::GetOrInsert,
 };
 use wgpu::{util::DeviceExt, Backends, Features, InstanceDescriptor, TextureView};
 use winit::window::Window;
@@ -49,6 +50,11 @@ pub struct WindowSurface {
     skybox_vertex_buffer: wgpu::Buffer,
     num_skybox_vertices: u32,
     texture_bind_group: wgpu::BindGroup,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    /// The last world shader that compiled, kept to rebuild the pipeline when only the textures change
+    world_shader: String,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    sky_bind_group_layout: wgpu::BindGroupLayout,
     camera: Camera,
     pub camera_controller: CameraController,
     camera_uniform: CameraUniform,
@@ -113,67 +119,8 @@ impl WindowSurface {
         };
         surface.configure(&device, &config);
 
-        let mut texture_index: Vec<Texture> = vec![];
-        let mut textures: HashMap<String, HashMap<String, usize>> = HashMap::new();
-
-        for ((namespace, name), texture) in resources.textures {
-            texture_index.push(texture);
-
-            textures
-                .get_or_insert(namespace, HashMap::new())
-                .insert(name.to_string(), texture_index.len() - 1);
-        }
-
-        let views: Vec<&TextureView> = texture_index.iter().map(|e| &e.view).collect();
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
-
-        let texture_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("texture_bind_group_layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            multisampled: false,
-                        },
-                        count: NonZeroU32::new(views.len() as u32),
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ],
-            });
-
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureViewArray(&views),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-            label: Some("texture_bind_group"),
-        });
-
-        let mut vertices = vec![];
-        let mut indices = vec![];
-
-        let mesh = generate_mesh(
-            Vec3::new(-16.0, -16.0, -16.0),
-            &[Voxel(0); CHUNK_SIZE as usize],
-        );
-        vertices.extend(mesh.0);
-        indices.extend(mesh.1);
+        let (texture_bind_group_layout, texture_bind_group) =
+            create_texture_bind_group(&device, &queue, resources.textures);
 
         // ============================= QUADS =============================
 
@@ -254,60 +201,25 @@ impl WindowSurface {
 
         let depth_texture = Texture::create_depth_texture(&device, &config, "depth_texture");
 
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[
-                    &texture_bind_group_layout,
-                    &camera_bind_group_layout,
-                    &sky_bind_group_layout,
-                ],
-                push_constant_ranges: &[],
-            });
-
-        let render_pipeline = create_pipeline(
-            true,
+        let render_pipeline = create_world_pipeline(
             &device,
-            &render_pipeline_layout,
-            &[Vertex::desc()],
+            &[
+                &texture_bind_group_layout,
+                &camera_bind_group_layout,
+                &sky_bind_group_layout,
+            ],
             config.format,
             client_config.debug.0,
-            wgpu::ShaderModuleDescriptor {
-                label: Some("Shader"),
-                source: wgpu::ShaderSource::Wgsl(resources.world_shader.into()),
-            },
+            resources.world_shader.clone(),
         );
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-        let num_indices = indices.len() as u32;
+        let (vertex_buffer, index_buffer, num_indices) = create_mesh_buffers(&device);
 
-        let skybox_render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&camera_bind_group_layout, &sky_bind_group_layout],
-                push_constant_ranges: &[],
-            });
-
-        let skybox_render_pipeline = create_pipeline(
-            false,
+        let skybox_render_pipeline = create_skybox_pipeline(
             &device,
-            &skybox_render_pipeline_layout,
-            &[SkyboxVertex::desc()],
+            &[&camera_bind_group_layout, &sky_bind_group_layout],
             config.format,
-            wgpu::PolygonMode::Fill,
-            wgpu::ShaderModuleDescriptor {
-                label: Some("Shader"),
-                source: wgpu::ShaderSource::Wgsl(resources.skybox_shader.into()),
-            },
+            resources.skybox_shader,
         );
 
         let skybox_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -332,6 +244,10 @@ impl WindowSurface {
             skybox_vertex_buffer,
             num_skybox_vertices,
             texture_bind_group,
+            texture_bind_group_layout,
+            world_shader: resources.world_shader,
+            camera_bind_group_layout,
+            sky_bind_group_layout,
             camera,
             camera_controller,
             camera_buffer,
@@ -345,6 +261,73 @@ impl WindowSurface {
         }
     }
 
+    /// Swaps in reloaded textures and rebuilds the pipelines
+    ///
+    /// Textures and shaders are validated separately, so a shader that doesn't compile keeps the old pipeline without
+    /// discarding correctly reloaded textures. Anything that fails validation is logged and the old version is kept
+    #[profiling::function]
+    pub fn reload(&mut self, client_config: &Config, resources: Resources) {
+        let textures = validated(&self.device, "textures", || {
+            create_texture_bind_group(&self.device, &self.queue, resources.textures)
+        });
+        let texture_layout = textures.as_ref().map_or(&self.texture_bind_group_layout, |(layout, ..)| layout);
+
+        let world_pipeline = |shader: &str| {
+            validated(&self.device, "world pipeline", || {
+                create_world_pipeline(
+                    &self.device,
+                    &[
+                        texture_layout,
+                        &self.camera_bind_group_layout,
+                        &self.sky_bind_group_layout,
+                    ],
+                    self.config.format,
+                    client_config.debug.0,
+                    shader.to_string(),
+                )
+            })
+        };
+        // New textures may change the bind group layout, so they need a pipeline even if the new shader is broken
+        let world = match world_pipeline(&resources.world_shader) {
+            Some(pipeline) => Some((pipeline, resources.world_shader.clone())),
+            None if textures.is_some() => {
+                world_pipeline(&self.world_shader).map(|pipeline| (pipeline, self.world_shader.clone()))
+            }
+            None => None,
+        };
+
+        match (world, textures) {
+            (Some((render_pipeline, world_shader)), textures) => {
+                self.render_pipeline = render_pipeline;
+                self.world_shader = world_shader;
+                if let Some((layout, bind_group)) = textures {
+                    self.texture_bind_group_layout = layout;
+                    self.texture_bind_group = bind_group;
+                }
+            }
+            (None, Some(_)) => error!("No world pipeline accepts the reloaded textures, keeping the old ones"),
+            (None, None) => {}
+        }
+
+        let skybox = validated(&self.device, "skybox pipeline", || {
+            create_skybox_pipeline(
+                &self.device,
+                &[&self.camera_bind_group_layout, &self.sky_bind_group_layout],
+                self.config.format,
+                resources.skybox_shader,
+            )
+        });
+        if let Some(skybox_render_pipeline) = skybox {
+            self.skybox_render_pipeline = skybox_render_pipeline;
+        }
+    }
+
+    /// Uploads a decoded image to the GPU
+    #[profiling::function]
+    pub fn create_texture(&self, image: &image::DynamicImage, label: &str) -> Result<Texture, image::ImageError> {
+        Texture::from_image(&self.device, &self.queue, image, Some(label))
+    }
+
     #[profiling::function]
     pub fn window(&self) -> &Window {
         &self.window
@@ -444,6 +427,148 @@ impl WindowSurface {
     }
 }
 
+/// Runs `create` inside a validation error scope, logging and discarding the result if wgpu reported an error
+fn validated<T>(device: &wgpu::Device, label: &str, create: impl FnOnce() -> T) -> Option<T> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create();
+    match block_on(device.pop_error_scope()) {
+        Some(e) => {
+            error!("Failed to reload the {label}: {e}");
+            None
+        }
+        None => Some(value),
+    }
+}
+
+/// Binds `resources` as a texture array, with a placeholder while there are none since the array can't be empty
+fn create_texture_bind_group(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    resources: Vec<((String, String), Texture)>,
+) -> (wgpu::BindGroupLayout, wgpu::BindGroup) {
+    let mut texture_index: Vec<Texture> = resources.into_iter().map(|(_, texture)| texture).collect();
+    if texture_index.is_empty() {
+        texture_index.push(Texture::placeholder(device, queue));
+    }
+
+    let views: Vec<&TextureView> = texture_index.iter().map(|e| &e.view).collect();
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+    let texture_bind_group_layout =
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("texture_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: NonZeroU32::new(views.len() as u32),
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+    let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &texture_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureViewArray(&views),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ],
+        label: Some("texture_bind_group"),
+    });
+
+    (texture_bind_group_layout, texture_bind_group)
+}
+
+/// The vertex and index buffers of the world mesh and its index count
+fn create_mesh_buffers(device: &wgpu::Device) -> (wgpu::Buffer, wgpu::Buffer, u32) {
+    let (vertices, indices) = generate_mesh(
+        Vec3::new(-16.0, -16.0, -16.0),
+        &[Voxel(0); CHUNK_SIZE as usize],
+    );
+
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Vertex Buffer"),
+        contents: bytemuck::cast_slice(&vertices),
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Index Buffer"),
+        contents: bytemuck::cast_slice(&indices),
+        usage: wgpu::BufferUsages::INDEX,
+    });
+
+    (vertex_buffer, index_buffer, indices.len() as u32)
+}
+
+fn create_world_pipeline(
+    device: &wgpu::Device,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    color_format: wgpu::TextureFormat,
+    polygon_mode: wgpu::PolygonMode,
+    shader: String,
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    create_pipeline(
+        true,
+        device,
+        &layout,
+        &[Vertex::desc()],
+        color_format,
+        polygon_mode,
+        wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader.into()),
+        },
+    )
+}
+
+fn create_skybox_pipeline(
+    device: &wgpu::Device,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    color_format: wgpu::TextureFormat,
+    shader: String,
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    create_pipeline(
+        false,
+        device,
+        &layout,
+        &[SkyboxVertex::desc()],
+        color_format,
+        wgpu::PolygonMode::Fill,
+        wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader.into()),
+        },
+    )
+}
+
 fn create_pipeline(
     depth: bool,
     device: &wgpu::Device,
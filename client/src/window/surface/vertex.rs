@@ -1,4 +1,4 @@
-use shared::math::{UVec2, UVec4};
+use shared::math::{UVec2, UVec4, Vec2};
 
 pub static SKYBOX_VERTICES: &[SkyboxVertex] = &[
     SkyboxVertex::new(-1.0, 1.0, -1.0),
@@ -88,7 +88,14 @@ impl Vertex {
     #[profiling::function]
     pub fn encode(light: UVec4, tex_coords: UVec2) -> u32 {
         (light.x << 28) | (light.y << 24) | (light.z << 20) | (light.w << 16) | (tex_coords.x << 15) | (tex_coords.y << 14)
-    }  
+    }
+
+    /// Like `encode`, but for texture coordinates between the corners, stored in steps of 1/64
+    #[profiling::function]
+    pub fn encode_uv(light: UVec4, tex_coords: Vec2) -> u32 {
+        let steps = (tex_coords.clamp(Vec2::ZERO, Vec2::ONE) * 64.0).round().as_uvec2();
+        Self::encode(light, UVec2::ZERO) | (steps.x << 7) | steps.y
+    }
 }
 
 #[repr(C)]
@@ -1,5 +1,5 @@
 use std::num::NonZeroU32;
-use image::{GenericImageView, ImageError};
+use image::{DynamicImage, GenericImageView, ImageError, Rgba, RgbaImage};
 use wgpu::TextureFormat;
 
 pub struct Texture {
@@ -54,6 +54,13 @@ impl Texture {
         }
     }
 
+    /// A 1×1 magenta texture that stands in until real textures are loaded
+    #[profiling::function]
+    pub fn placeholder(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([255, 0, 255, 255])));
+        Self::from_image(device, queue, &image, Some("placeholder")).expect("1×1 RGBA images are always valid")
+    }
+
     #[profiling::function]
     pub fn from_bytes(
         device: &wgpu::Device,
@@ -1,11 +1,10 @@
-use std::{thread, vec};
+use std::{sync::Arc, thread, vec};
 
 use client::{
-    config::{Debug, Gamma, MeshingDistance, PolygonMode},
+    config::{Debug, Gamma, HotReload, MeshingDistance, PolygonMode},
     input::{InputType, Key}, Resources, declare_block,
 };
-use server::config::{LoadingDistance, SimulationDistance};
-use shared::{log::{LevelFilter, info}, resources, types::item::Item};
+use shared::{log::{LevelFilter, error, info}, resources::{self, Vfs}, types::item::Item};
 use simple_logger::SimpleLogger;
 
 #[profiling::function]
@@ -26,14 +25,17 @@ fn main() {
         .init()
         .unwrap();
 
+    // Resources are looked up next to the executable when they're shipped with it and in the working directory otherwise,
+    // run `cargo run -- --root <dir>` to pick another
+    resources::init_root("example");
+
     // Server
     /*thread::spawn(|| {
         profiling::register_thread!("Server");
+        // Settings are read from server.toml, which is written with the defaults on first run
         server::init(
-            server::config::Config {
-                loading_distance: LoadingDistance(12),
-                simulation_distance: SimulationDistance(14),
-            },
+            |key, _definition| todo!("Build the block {key}"),
+            |key, _definition| todo!("Build the item {key}"),
             |_server, _server_io, _modules| {
             },
             |_state, _server, _server_io| {
@@ -46,6 +48,7 @@ fn main() {
             debug: Debug(PolygonMode::Fill),
             meshing_distance: MeshingDistance(12),
             gamma: Gamma(1.0),
+            hot_reload: HotReload(vec!["example".into()]),
         },
         |client, _client_io, _modules| {
             client.input.add_actions(vec![
@@ -61,12 +64,37 @@ fn main() {
         |_state, _client, _client_io| {
         },
         |_state, _client, _client_io| {
-            let textures = vec![]; // TODO: load textures
+            let mut vfs = Vfs::game();
+            if let Err(e) = vfs.mount_addon("example", "example") {
+                error!("Failed to mount example resources: {e}");
+            }
+
+            let texture_files = vfs
+                .list("example:textures")
+                .map(|files| {
+                    files
+                        .into_iter()
+                        .filter_map(|file| {
+                            let name = file.path.strip_prefix("textures/")?.strip_suffix(".png")?.to_string();
+                            Some(((file.namespace.clone(), name), file.to_string()))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let read = |path: &str| {
+                vfs.read_string(path).unwrap_or_else(|e| {
+                    error!("Failed to read {path}: {e}");
+                    String::new()
+                })
+            };
 
             Resources {
-                world_shader: resources::read_string("example/world_shader.wgsl").unwrap(),
-                skybox_shader: resources::read_string("example/skybox_shader.wgsl").unwrap(),
-                textures,
+                world_shader: read("example:world_shader.wgsl"),
+                skybox_shader: read("example:skybox_shader.wgsl"),
+                textures: vec![],
+                texture_files,
+                vfs: Arc::new(vfs),
             }
         },
         |_state, _client, _client_io| {
@@ -66,8 +66,8 @@ fn decode(i: u32) -> Data {
             f32((i << 12u) >> 28u) / 15.0,
         ),
         vec2<f32>(
-            f32((i << 16u) >> 31u),
-            f32((i << 17u) >> 31u),
+            f32((i << 16u) >> 31u) + f32((i << 18u) >> 25u) / 64.0,
+            f32((i << 17u) >> 31u) + f32((i << 25u) >> 25u) / 64.0,
         )
     );
 }
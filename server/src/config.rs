@@ -1,8 +1,94 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use shared::{resources::{self, Versioned}, toml};
+
+/// Where the server reads its config from at startup
+pub const CONFIG_PATH: &str = "server.toml";
+
+#[derive(Serialize, Deserialize)]
 pub struct Config {
     pub loading_distance: LoadingDistance,
     pub simulation_distance: SimulationDistance,
+    pub random_tick_speed: RandomTickSpeed,
+    pub max_update_depth: MaxUpdateDepth,
+    pub seed: Seed,
+    pub backups: Backups,
 }
 
+impl Config {
+    /// Loads the config at `path`, writing the defaults on first run
+    pub fn load(path: impl AsRef<Path>) -> resources::Result<Self> {
+        resources::load_or_default_versioned(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> resources::Result<()> {
+        resources::save_versioned(path, self, self.backups.0.into())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            loading_distance: LoadingDistance(12),
+            simulation_distance: SimulationDistance(14),
+            random_tick_speed: RandomTickSpeed(3),
+            max_update_depth: MaxUpdateDepth(64),
+            seed: Seed(0),
+            backups: Backups::default(),
+        }
+    }
+}
+
+impl Versioned for Config {
+    const SCHEMA_VERSION: u32 = 2;
+
+    fn migrate(data: &mut toml::Table, version: u32) -> Result<(), String> {
+        match version {
+            // Unversioned files may miss any setting added since, those start out at their defaults
+            0 => {
+                let defaults = match toml::Value::try_from(Config::default()) {
+                    Ok(toml::Value::Table(defaults)) => defaults,
+                    Ok(_) => return Err("Config defaults must serialize to a table".to_string()),
+                    Err(e) => return Err(e.to_string()),
+                };
+                for (field, value) in defaults {
+                    data.entry(field).or_insert(value);
+                }
+                Ok(())
+            }
+            1 => {
+                data.insert("backups".to_string(), toml::Value::Integer(Backups::default().0.into()));
+                Ok(())
+            }
+            _ => Err(format!("No migration from schema version {version}")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct SimulationDistance(pub u16);
 
-pub struct LoadingDistance(pub u16);
\ No newline at end of file
+#[derive(Serialize, Deserialize)]
+pub struct LoadingDistance(pub u16);
+
+/// Random updates per loaded chunk per tick
+#[derive(Serialize, Deserialize)]
+pub struct RandomTickSpeed(pub u16);
+
+/// How far a chain of neighbor updates can spread in one tick before the rest waits for the next, at least 1
+#[derive(Serialize, Deserialize)]
+pub struct MaxUpdateDepth(pub u16);
+
+#[derive(Serialize, Deserialize)]
+pub struct Seed(pub u64);
+
+/// How many previous copies of the config and world files are kept
+#[derive(Serialize, Deserialize)]
+pub struct Backups(pub u16);
+
+impl Default for Backups {
+    fn default() -> Self {
+        Self(3)
+    }
+}
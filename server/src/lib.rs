@@ -1,44 +1,170 @@
-// TODO: Rewrite to be like client
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
 
-use config::Config;
+use config::{Config, CONFIG_PATH};
+use shared::math::IVec3;
+use shared::log::error;
+use shared::lua::Lua;
+use shared::packets::Packet;
 use shared::registry::Registry;
+use shared::resources::Vfs;
+use shared::types::{block::{self, BlockDefinition}, context::EntityId, inventory::Inventory, item::{self, ItemDefinition}, Key};
+use simulation::Simulation;
+use terrain::Terrain;
+use world::{WorldInfo, WORLD_DIRECTORY};
 
 pub mod pathfinding;
 pub mod terrain;
 pub mod config;
 pub mod network;
+pub mod simulation;
+pub mod world;
 
-pub struct Server {
+/// Ticks between saves of the whole world
+pub const AUTOSAVE_INTERVAL: u64 = 6000;
+
+pub struct Server<
+    T: Clone + Debug + Eq + Hash + PartialEq,
+    B: block::Block,
+    I: item::Item,
+    D,
+> {
     pub config: Config,
-    //pub registry: Registry<D>,
+    pub world: WorldInfo,
+    pub registry: Registry<T, B, I, D>,
+    /// The base game with the installed addons on top, everything in it is registered at startup
+    pub vfs: Vfs,
+    /// The scripting state shared by addons, its `tags` global is set once the registry is frozen
+    pub lua: Lua,
+    pub terrain: Terrain,
+    pub simulation: Simulation,
+    /// Chunk positions the world is simulated around, such as those of players
+    pub observers: Vec<IVec3>,
+    pub inventories: BTreeMap<EntityId, Inventory>,
+    /// Packets for every client, queued up by ticks until whoever sends them to clients takes them out
+    pub outbound: Vec<Packet>,
+}
+
+impl<T, B, I, D> Server<T, B, I, D>
+where
+    T: Clone + Debug + Eq + Hash + PartialEq,
+    B: block::Block,
+    I: item::Item,
+{
+    /// Saves the config, the world info and every loaded chunk, keeping as many backups as the config asks for.
+    /// Problems are logged, so one broken file doesn't stop the rest from being saved
+    #[profiling::function]
+    pub fn save(&mut self) {
+        let backups = self.config.backups.0.into();
+        if let Err(e) = self.config.save(CONFIG_PATH) {
+            error!("Failed to save the config: {e}");
+        }
+
+        self.world.time = self.simulation.time;
+        if let Err(e) = self.world.save(WORLD_DIRECTORY, backups) {
+            error!("Failed to save the world info: {e}");
+        }
+        for position in self.terrain.chunk_positions() {
+            if let Err(e) = self.terrain.save_chunk(WORLD_DIRECTORY, position, &self.registry, self.simulation.time, backups) {
+                error!("Failed to save the chunk at {position}: {e}");
+            }
+        }
+    }
 }
 
 pub struct ServerIO {
 }
 
+/// Loads the config from [`CONFIG_PATH`] and the world from [`WORLD_DIRECTORY`].
+/// `block` and `item` build the blocks and items addons define, they're all registered before `init` runs
 #[profiling::function]
-pub fn init<I, F, S>(config: Config, init: I, frame: F)
-where
-    I: FnOnce(&mut Server, &ServerIO, ()) -> S,
-    F: Fn(&mut S, &mut Server, &ServerIO),
+pub fn init<
+    State,
+    CustomType: Clone + Debug + Eq + Hash + PartialEq,
+    Block: block::Block,
+    Item: item::Item,
+    CustomData,
+    FB,
+    FI,
+    I,
+    F,
+>(
+    block: FB,
+    item: FI,
+    init: I,
+    frame: F,
+) where
+    FB: Fn(&Key, BlockDefinition) -> Block,
+    FI: Fn(&Key, ItemDefinition) -> Item,
+    I: FnOnce(&mut Server<CustomType, Block, Item, CustomData>, &ServerIO, ()) -> State,
+    F: Fn(&mut State, &mut Server<CustomType, Block, Item, CustomData>, &ServerIO),
 {
-    //let registry = Registry::new();
+    let config = Config::load(CONFIG_PATH).unwrap_or_else(|e| {
+        error!("Failed to load the config, using the defaults: {e}");
+        Config::default()
+    });
+    let world = WorldInfo::load_or_create(WORLD_DIRECTORY, config.seed.0).unwrap_or_else(|e| {
+        error!("Failed to load the world info, using the config's seed: {e}");
+        WorldInfo { seed: config.seed.0, time: 0 }
+    });
+
+    let vfs = Vfs::game();
+    let mut registry = Registry::new();
+    if let Err(errors) = registry.load_addons(&vfs, block, item) {
+        for e in errors {
+            error!("Failed to load {e}");
+        }
+    }
+    let mut simulation = Simulation::new(world.seed);
+    simulation.time = world.time;
 
     let mut server = Server {
         config,
-        //registry,
+        world,
+        registry,
+        vfs,
+        lua: Lua::new(),
+        terrain: Terrain::new(),
+        simulation,
+        observers: vec![],
+        inventories: BTreeMap::new(),
+        outbound: vec![],
     };
 
     let server_io = ServerIO {
     };
 
     let mut state = init(&mut server, &server_io, ());
+    server.registry.freeze();
+    if let Err(e) = server.registry.register_lua(&server.lua) {
+        error!("Failed to expose registry tags to Lua: {e}");
+    }
 
     loop {
         tick(&frame, &mut state, &mut server, &server_io);
     }
 }
 
-fn tick<F, S>(frame: &F, state: &mut S, server: &mut Server, server_io: &ServerIO) where F: Fn(&mut S, &mut Server, &ServerIO) {
+fn tick<F, S, T, B, I, D>(frame: &F, state: &mut S, server: &mut Server<T, B, I, D>, server_io: &ServerIO)
+where
+    F: Fn(&mut S, &mut Server<T, B, I, D>, &ServerIO),
+    T: Clone + Debug + Eq + Hash + PartialEq,
+    B: block::Block,
+    I: item::Item,
+{
+    server.simulation.tick(
+        &mut server.terrain,
+        &server.registry,
+        &server.config,
+        &server.observers,
+        &server.inventories,
+    );
+
+    server.outbound.extend(server.terrain.sync_block_entities().into_iter().map(Packet::BlockEntity));
     frame(state, server, server_io);
-}
\ No newline at end of file
+
+    if server.simulation.time.is_multiple_of(AUTOSAVE_INTERVAL) {
+        server.save();
+    }
+}
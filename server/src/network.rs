@@ -4,7 +4,7 @@ use std::{
 };
 
 use shared::{
-    log::debug,
+    log::{debug, warn},
     network::{
         server::{self, Server},
         SendMode,
@@ -65,9 +65,15 @@ impl<A: net::ToSocketAddrs>
                         let (id_bytes, data) = packet_data.split_at(4);
                         let id = u32::from_be_bytes(id_bytes.try_into().unwrap_or_default());
 
-                        self.inbound_packets
-                            .send((client_address, Packet::deserialize(id, data).unwrap()))
-                            .ignore();
+                        match Packet::deserialize(id, data) {
+                            Ok(packet) => self.inbound_packets.send((client_address, packet)).ignore(),
+                            Err(e) => {
+                                warn!("[{:?}] sent an invalid packet, disconnecting: {}", client_address, e);
+                                if let Some(client) = self.server.client(&client_address) {
+                                    client.borrow_mut().disconnect();
+                                }
+                            }
+                        }
 
                         debug!("[{:?}] received \"{:?}\"", client_address, packet_data);
                     }
@@ -0,0 +1,65 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use shared::{
+    math::IVec3,
+    rand::rngs::StdRng,
+    registry::Registry,
+    types::{
+        block::Block,
+        context::{BlockContext, Context, States, Update, WorldAccess},
+        item::Item,
+    },
+};
+
+use crate::{
+    config::Config,
+    terrain::{Terrain, CHUNK_SIZE},
+};
+
+use super::observer_distance;
+
+/// Calls `Block::on_block_entity_tick` for every block entity in chunks within `SimulationDistance`.
+/// The entity is taken out of the world for the call and put back afterwards, unless its block was replaced
+#[profiling::function]
+pub fn tick_block_entities<T, B, I, D>(
+    terrain: &mut Terrain,
+    registry: &Registry<T, B, I, D>,
+    config: &Config,
+    observers: &[IVec3],
+    random: &mut StdRng,
+    updates: &mut Vec<Update>,
+) where
+    T: Clone + Debug + Eq + Hash + PartialEq,
+    B: Block,
+    I: Item,
+{
+    for chunk in terrain.chunk_positions() {
+        let Some(distance) = observer_distance(chunk, observers) else { return };
+        if distance > i32::from(config.simulation_distance.0) {
+            continue;
+        }
+
+        let Some(locals) = terrain.chunk(chunk).map(|chunk| chunk.block_entity_positions()) else { continue };
+        for local in locals {
+            let position = chunk * CHUNK_SIZE + local;
+            let state = terrain.get_state(position);
+            let Some(block) = registry.block(state) else { continue };
+            let Some(mut entity) = terrain.chunk_mut(chunk).and_then(|chunk| chunk.take_block_entity(local)) else {
+                continue;
+            };
+
+            let context = Context::new(terrain, registry, random, None, updates);
+            block.on_block_entity_tick(&mut BlockContext::new(position, context), &mut entity);
+
+            if terrain.block_entity(position).is_some() {
+                continue;
+            }
+            if registry.same_block(state, terrain.get_state(position)) {
+                terrain.set_block_entity(position, Some(entity));
+            } else if let Some(chunk) = terrain.chunk_mut(chunk) {
+                chunk.mark_block_entity_removed(local);
+            }
+        }
+    }
+}
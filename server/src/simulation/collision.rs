@@ -0,0 +1,44 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use shared::{
+    math::Vec3,
+    rand::rngs::StdRng,
+    registry::Registry,
+    shape::{self, Movement},
+    types::{
+        block::Block,
+        context::{BlockContext, Context, EntityId, Update, WorldAccess},
+        item::Item,
+    },
+    util::BoundingBox,
+};
+
+use crate::terrain::Terrain;
+
+/// Moves an entity's bounds through the terrain and calls `Block::on_collision` for every block it ran into
+#[profiling::function]
+pub fn move_entity<T, B, I, D>(
+    terrain: &mut Terrain,
+    registry: &Registry<T, B, I, D>,
+    entity: EntityId,
+    bounds: BoundingBox,
+    velocity: Vec3,
+    random: &mut StdRng,
+    updates: &mut Vec<Update>,
+) -> Movement
+where
+    T: Clone + Debug + Eq + Hash + PartialEq,
+    B: Block,
+    I: Item,
+{
+    let movement = shape::sweep(terrain, |state| registry.collision_shape(state), bounds, velocity);
+
+    for position in &movement.touched {
+        let Some(block) = registry.block(terrain.get_state(*position)) else { continue };
+        let context = Context::new(terrain, registry, random, Some(entity), updates);
+        block.on_collision(&mut BlockContext::new(*position, context));
+    }
+
+    movement
+}
@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use shared::{
+    rand::rngs::StdRng,
+    registry::Registry,
+    types::{
+        block::Block,
+        context::{Context, EntityId, Update},
+        inventory::Inventory,
+        item::Item,
+    },
+};
+
+use crate::terrain::Terrain;
+
+/// Calls `Item::on_update` for every stack in every inventory, with where in the inventory the stack is
+#[profiling::function]
+pub fn update_items<T, B, I, D>(
+    terrain: &mut Terrain,
+    registry: &Registry<T, B, I, D>,
+    inventories: &BTreeMap<EntityId, Inventory>,
+    random: &mut StdRng,
+    updates: &mut Vec<Update>,
+) where
+    T: Clone + Debug + Eq + Hash + PartialEq,
+    B: Block,
+    I: Item,
+{
+    for (entity, inventory) in inventories {
+        for (slot, stack) in inventory.stacks() {
+            let Some(item) = registry.item(&stack.item) else { continue };
+            let mut context = Context::new(terrain, registry, random, Some(*entity), updates);
+            item.on_update(&mut context, inventory.location(slot));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use shared::{
+    math::{IVec3, Vec3},
+    rand::{rngs::StdRng, SeedableRng},
+    registry::Registry,
+    shape::Movement,
+    types::{
+        block::Block,
+        context::{BlockContext, Context, EntityId, Update, WorldAccess},
+        inventory::Inventory,
+        item::Item,
+        loot::{LootContext, LootDrop},
+        state::StateId,
+    },
+    util::BoundingBox,
+};
+
+use crate::{config::Config, terrain::Terrain};
+
+use self::neighbor_update::NeighborUpdates;
+
+pub mod block_entity_tick;
+pub mod collision;
+pub mod item_update;
+pub mod neighbor_update;
+pub mod random_tick;
+pub mod scheduled_update;
+
+/// Drives block updates. Everything random is drawn from one seeded source, so a tick is reproducible
+pub struct Simulation {
+    pub time: u64,
+    random: StdRng,
+    updates: Vec<Update>,
+    neighbor_updates: NeighborUpdates,
+}
+
+impl Simulation {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            time: 0,
+            random: StdRng::seed_from_u64(seed),
+            updates: vec![],
+            neighbor_updates: NeighborUpdates::new(),
+        }
+    }
+
+    /// `observers` are the chunk positions the world is simulated around
+    #[profiling::function]
+    pub fn tick<T, B, I, D>(
+        &mut self,
+        terrain: &mut Terrain,
+        registry: &Registry<T, B, I, D>,
+        config: &Config,
+        observers: &[IVec3],
+        inventories: &BTreeMap<EntityId, Inventory>,
+    ) where
+        T: Clone + Debug + Eq + Hash + PartialEq,
+        B: Block,
+        I: Item,
+    {
+        scheduled_update::run_scheduled(terrain, registry, config, observers, self.time, &mut self.random, &mut self.updates);
+        random_tick::random_tick(terrain, registry, config, observers, &mut self.random, &mut self.updates);
+        block_entity_tick::tick_block_entities(terrain, registry, config, observers, &mut self.random, &mut self.updates);
+        item_update::update_items(terrain, registry, inventories, &mut self.random, &mut self.updates);
+        self.neighbor_updates.process(terrain, registry, config, observers, &mut self.random, &mut self.updates);
+        scheduled_update::schedule(terrain, self.time, &mut self.updates);
+
+        self.time += 1;
+    }
+
+    /// Breaks the block at a position, calling `Block::on_destroy` before it turns into air.
+    /// Returns what its loot table dropped, rolled from the simulation's seeded random source
+    pub fn destroy_block<T, B, I, D>(
+        &mut self,
+        terrain: &mut Terrain,
+        registry: &Registry<T, B, I, D>,
+        position: IVec3,
+        loot: &LootContext,
+        entity: Option<EntityId>,
+    ) -> Vec<LootDrop>
+    where
+        T: Clone + Debug + Eq + Hash + PartialEq,
+        B: Block,
+        I: Item,
+    {
+        let state = terrain.get_state(position);
+        let Some(block) = registry.block(state) else { return vec![] };
+
+        let context = Context::new(terrain, registry, &mut self.random, entity, &mut self.updates);
+        let mut context = BlockContext::new(position, context);
+        block.on_destroy(&mut context);
+        context.replace(StateId::AIR);
+
+        registry.block_drops(state, loot, &mut self.random)
+    }
+
+    /// Moves an entity through the terrain, stopping at block collision shapes.
+    /// Whatever the blocks it runs into do in response is processed on the next tick
+    pub fn move_entity<T, B, I, D>(
+        &mut self,
+        terrain: &mut Terrain,
+        registry: &Registry<T, B, I, D>,
+        entity: EntityId,
+        bounds: BoundingBox,
+        velocity: Vec3,
+    ) -> Movement
+    where
+        T: Clone + Debug + Eq + Hash + PartialEq,
+        B: Block,
+        I: Item,
+    {
+        collision::move_entity(terrain, registry, entity, bounds, velocity, &mut self.random, &mut self.updates)
+    }
+}
+
+/// Chebyshev distance in chunks to the closest observer
+pub(crate) fn observer_distance(chunk: IVec3, observers: &[IVec3]) -> Option<i32> {
+    observers.iter().map(|observer| (chunk - *observer).abs().max_element()).min()
+}
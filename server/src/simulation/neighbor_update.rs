@@ -0,0 +1,93 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use shared::{
+    direction::Direction,
+    math::IVec3,
+    rand::rngs::StdRng,
+    registry::Registry,
+    types::{
+        block::Block,
+        context::{BlockContext, Context, Update, WorldAccess},
+        item::Item,
+    },
+};
+
+use crate::{config::Config, terrain::{chunk_position, Terrain}};
+
+use super::observer_distance;
+
+/// Breadth first propagation of `Update::Neighbors` to `Block::on_neighbor_update`
+#[derive(Default)]
+pub struct NeighborUpdates {
+    /// Position to notify, the face the change came from and how many updates deep it is
+    queue: VecDeque<(IVec3, Direction, u16)>,
+    seen: HashSet<(IVec3, Direction)>,
+}
+
+impl NeighborUpdates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves every `Update::Neighbors` out of `updates` and processes it along with whatever it causes.
+    /// Cascades deeper than `MaxUpdateDepth` continue next tick, anything else is left in `updates`.
+    ///
+    /// Deferred updates start the next tick at depth 1, so a chain that keeps going advances by at most
+    /// `MaxUpdateDepth` steps per tick instead of being dropped
+    #[profiling::function]
+    pub fn process<T, B, I, D>(
+        &mut self,
+        terrain: &mut Terrain,
+        registry: &Registry<T, B, I, D>,
+        config: &Config,
+        observers: &[IVec3],
+        random: &mut StdRng,
+        updates: &mut Vec<Update>,
+    ) where
+        T: Clone + Debug + Eq + Hash + PartialEq,
+        B: Block,
+        I: Item,
+    {
+        self.seen.clear();
+        self.enqueue(updates, 0);
+
+        // A depth of 0 would defer everything forever
+        let max_depth = config.max_update_depth.0.max(1);
+        let mut deferred = VecDeque::new();
+        while let Some((position, from, depth)) = self.queue.pop_front() {
+            if depth > max_depth {
+                deferred.push_back((position, from, 1));
+                continue;
+            }
+            if !self.seen.insert((position, from)) {
+                continue;
+            }
+            let Some(block) = registry.block(terrain.get_state(position)) else { continue };
+
+            let visible = observer_distance(chunk_position(position), observers)
+                .is_some_and(|distance| distance <= i32::from(config.loading_distance.0));
+
+            let mut caused = vec![];
+            let context = Context::new(terrain, registry, random, None, &mut caused);
+            block.on_neighbor_update(&mut BlockContext::new(position, context), from, visible);
+
+            self.enqueue(&mut caused, depth);
+            updates.extend(caused);
+        }
+
+        self.queue = deferred;
+    }
+
+    /// Queues the six neighbors of every changed position, telling each which face the change came from
+    fn enqueue(&mut self, updates: &mut Vec<Update>, depth: u16) {
+        updates.retain(|update| {
+            let Update::Neighbors(position) = update else { return true };
+            for direction in Direction::iter() {
+                self.queue.push_back((*position + direction.get_normal(), direction.opposite(), depth.saturating_add(1)));
+            }
+            false
+        });
+    }
+}
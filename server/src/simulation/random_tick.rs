@@ -0,0 +1,148 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use shared::{
+    math::IVec3,
+    rand::{rngs::StdRng, Rng},
+    registry::Registry,
+    types::{
+        block::Block,
+        context::{BlockContext, Context, Update, WorldAccess},
+        item::Item,
+    },
+};
+
+use crate::{
+    config::Config,
+    terrain::{delinearize, Terrain, CHUNK_SIZE, CHUNK_VOLUME},
+};
+
+use super::observer_distance;
+
+/// Calls `Block::on_random_update` at `RandomTickSpeed` random positions in every chunk within `SimulationDistance`
+/// of an observer. Blocks count as visible while their chunk is also within `LoadingDistance`
+#[profiling::function]
+pub fn random_tick<T, B, I, D>(
+    terrain: &mut Terrain,
+    registry: &Registry<T, B, I, D>,
+    config: &Config,
+    observers: &[IVec3],
+    random: &mut StdRng,
+    updates: &mut Vec<Update>,
+) where
+    T: Clone + Debug + Eq + Hash + PartialEq,
+    B: Block,
+    I: Item,
+{
+    for chunk in terrain.chunk_positions() {
+        let Some(distance) = observer_distance(chunk, observers) else { return };
+        if distance > i32::from(config.simulation_distance.0) {
+            continue;
+        }
+        let visible = distance <= i32::from(config.loading_distance.0);
+
+        for _ in 0..config.random_tick_speed.0 {
+            let position = chunk * CHUNK_SIZE + delinearize(random.gen_range(0..CHUNK_VOLUME));
+            let Some(block) = registry.block(terrain.get_state(position)) else { continue };
+
+            let context = Context::new(terrain, registry, random, None, updates);
+            block.on_random_update(&mut BlockContext::new(position, context), visible);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use shared::{
+        direction::Direction,
+        types::{
+            block::PathfindingState,
+            item::{Hand, Location, Target},
+            state::StateId,
+            BuiltinData, BuiltinType, Data, Id, Type,
+        },
+    };
+
+    use crate::{
+        config::{LoadingDistance, RandomTickSpeed, SimulationDistance},
+        simulation::Simulation,
+        terrain::Chunk,
+    };
+
+    use super::*;
+
+    /// Remembers where it was randomly ticked
+    #[derive(Default)]
+    struct Logged(RefCell<Vec<(IVec3, bool)>>);
+
+    impl Block for Logged {
+        fn on_random_update(&self, ctx: &mut BlockContext, visible: bool) {
+            self.0.borrow_mut().push((ctx.position, visible));
+        }
+        fn on_neighbor_update(&self, _ctx: &mut BlockContext, _from: Direction, _visible: bool) {}
+        fn on_scheduled_update(&self, _ctx: &mut BlockContext, _visible: bool) {}
+        fn on_place(&self, _ctx: &mut BlockContext) {}
+        fn on_destroy(&self, _ctx: &mut BlockContext) {}
+        fn on_interact(&self, _ctx: &mut BlockContext) {}
+        fn on_collision(&self, _ctx: &mut BlockContext) {}
+        fn can_place(&self, _ctx: &BlockContext) -> bool {
+            true
+        }
+        fn can_interact(&self, _ctx: &BlockContext) -> bool {
+            true
+        }
+        fn can_pathfind(&self, _ctx: &BlockContext) -> PathfindingState {
+            PathfindingState::Solid
+        }
+    }
+
+    struct NoItem;
+
+    impl Item for NoItem {
+        fn on_use(&self, _ctx: &mut Context, _target: Target, _hand: Hand) {}
+        fn on_update(&self, _ctx: &mut Context, _location: Location) {}
+    }
+
+    /// Ticks a row of four chunks filled with `Logged` blocks once, observed from the first one
+    fn ticked(seed: u64) -> Vec<(IVec3, bool)> {
+        let mut registry: Registry<u8, Logged, NoItem, ()> = Registry::new();
+        let id = Id::parse(Type::Builtin(BuiltinType::Block), "test:logged").unwrap();
+        registry.register(id, Data::Builtin(BuiltinData::Block(Logged::default()))).unwrap();
+        registry.freeze();
+
+        let mut terrain = Terrain::new();
+        for x in 0..4 {
+            let mut chunk = Chunk::new();
+            for index in 0..CHUNK_VOLUME {
+                chunk.set(delinearize(index), StateId(1));
+            }
+            terrain.load(IVec3::new(x, 0, 0), chunk);
+        }
+
+        let config = Config {
+            loading_distance: LoadingDistance(1),
+            simulation_distance: SimulationDistance(2),
+            random_tick_speed: RandomTickSpeed(3),
+            ..Config::default()
+        };
+        let mut simulation = Simulation::new(seed);
+        simulation.tick(&mut terrain, &registry, &config, &[IVec3::ZERO], &Default::default());
+
+        let ticked = registry.block(StateId(1)).unwrap().0.take();
+        ticked
+    }
+
+    #[test]
+    fn seeded() {
+        let first = ticked(5);
+        assert_eq!(first, ticked(5));
+        assert_ne!(first, ticked(6));
+
+        // Three chunks are within the simulation distance, two of them within the loading distance
+        assert_eq!(first.len(), 9);
+        assert_eq!(first.iter().filter(|(_, visible)| *visible).count(), 6);
+        assert!(first.iter().all(|(position, visible)| position.x < 3 * CHUNK_SIZE && *visible == (position.x < 2 * CHUNK_SIZE)));
+    }
+}
@@ -0,0 +1,150 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use shared::{
+    log,
+    math::IVec3,
+    rand::rngs::StdRng,
+    registry::Registry,
+    types::{
+        block::Block,
+        context::{BlockContext, Context, States, Update, WorldAccess},
+        item::Item,
+        state::StateId,
+    },
+};
+
+use crate::{
+    config::Config,
+    terrain::{chunk_position, delinearize, linearize, local_position, Terrain, CHUNK_SIZE},
+};
+
+use super::observer_distance;
+
+/// A pending `Block::on_scheduled_update`. Ordered by due tick, then priority, then the order it was scheduled in
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct ScheduledTick {
+    /// The `Simulation::time` the tick runs at
+    pub due: u64,
+    pub priority: i32,
+    sequence: u64,
+    /// Chunk local index of the position
+    index: u32,
+    /// The state that requested the tick, it only runs while the same block is still there
+    pub state: StateId,
+}
+
+impl ScheduledTick {
+    pub fn local_position(&self) -> IVec3 {
+        delinearize(self.index as usize)
+    }
+}
+
+/// The scheduled ticks of one chunk, saved along with it
+#[derive(Clone, Debug, Default)]
+pub struct ScheduledTicks {
+    queue: BinaryHeap<Reverse<ScheduledTick>>,
+    next_sequence: u64,
+}
+
+impl ScheduledTicks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn schedule(&mut self, local: IVec3, due: u64, priority: i32, state: StateId) {
+        self.queue.push(Reverse(ScheduledTick {
+            due,
+            priority,
+            sequence: self.next_sequence,
+            index: linearize(local) as u32,
+            state,
+        }));
+        self.next_sequence += 1;
+    }
+
+    /// Every pending tick in the order they run
+    pub fn ticks(&self) -> Vec<&ScheduledTick> {
+        let mut ticks: Vec<&ScheduledTick> = self.queue.iter().map(|Reverse(tick)| tick).collect();
+        ticks.sort();
+        ticks
+    }
+
+    /// Removes and returns the earliest tick if it is due at `time`
+    pub fn pop_due(&mut self, time: u64) -> Option<ScheduledTick> {
+        if self.queue.peek()?.0.due > time {
+            return None;
+        }
+        self.queue.pop().map(|Reverse(tick)| tick)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// Moves every `Update::Scheduled` out of `updates` into the queue of the chunk it belongs to.
+/// A delay of `0` still waits until the next tick, ticks for unloaded chunks are dropped
+#[profiling::function]
+pub fn schedule(terrain: &mut Terrain, time: u64, updates: &mut Vec<Update>) {
+    updates.retain(|update| {
+        let Update::Scheduled { position, state, delay, priority } = *update else { return true };
+        match terrain.chunk_mut(chunk_position(position)) {
+            Some(chunk) => {
+                let due = time + u64::from(delay.max(1));
+                chunk.scheduled_mut().schedule(local_position(position), due, priority, state);
+            }
+            None => log::debug!("Dropped scheduled tick at unloaded position {position}"),
+        }
+        false
+    });
+}
+
+/// Calls `Block::on_scheduled_update` for every tick due at `time` in chunks within `SimulationDistance`.
+/// Ticks in chunks further away stay queued until an observer comes close again, ticks whose block was replaced
+/// since they were scheduled are dropped
+#[profiling::function]
+pub fn run_scheduled<T, B, I, D>(
+    terrain: &mut Terrain,
+    registry: &Registry<T, B, I, D>,
+    config: &Config,
+    observers: &[IVec3],
+    time: u64,
+    random: &mut StdRng,
+    updates: &mut Vec<Update>,
+) where
+    T: Clone + Debug + Eq + Hash + PartialEq,
+    B: Block,
+    I: Item,
+{
+    let mut due = vec![];
+    for chunk in terrain.chunk_positions() {
+        let Some(distance) = observer_distance(chunk, observers) else { return };
+        if distance > i32::from(config.simulation_distance.0) {
+            continue;
+        }
+        let visible = distance <= i32::from(config.loading_distance.0);
+
+        let Some(scheduled) = terrain.chunk_mut(chunk).map(|chunk| chunk.scheduled_mut()) else { continue };
+        while let Some(tick) = scheduled.pop_due(time) {
+            due.push((chunk * CHUNK_SIZE + tick.local_position(), tick, visible));
+        }
+    }
+    due.sort_by_key(|(_, tick, _)| (tick.due, tick.priority));
+
+    for (position, tick, visible) in due {
+        let state = terrain.get_state(position);
+        if !registry.same_block(tick.state, state) {
+            continue;
+        }
+        let Some(block) = registry.block(state) else { continue };
+        let context = Context::new(terrain, registry, random, None, updates);
+        block.on_scheduled_update(&mut BlockContext::new(position, context), visible);
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use shared::{
+    log,
+    math::IVec3,
+    packets::block_entity::BlockEntityPacket,
+    registry::Registry,
+    resources::{self, ResourceError},
+    types::{
+        block::Block,
+        block_entity::BlockEntity,
+        context::WorldAccess,
+        item::Item,
+        state::{StateId, StateKey},
+    },
+};
+
+use crate::simulation::scheduled_update::ScheduledTicks;
+
+pub const CHUNK_SIZE: i32 = 32;
+pub const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// A cubic section of the world, along with the block entities and ticks scheduled inside it
+pub struct Chunk {
+    states: Vec<StateId>,
+    /// Keyed by chunk index
+    block_entities: HashMap<u32, BlockEntity>,
+    /// Local positions of block entities removed since the last sync
+    removed_block_entities: Vec<IVec3>,
+    scheduled: ScheduledTicks,
+}
+
+/// How a chunk is written to disk.
+///
+/// States are indices into a palette of `StateKey`s, as state ids change whenever the registered blocks do.
+/// Scheduled ticks keep the number of ticks left, so they don't depend on `Simulation::time`
+#[derive(Serialize, Deserialize)]
+struct SavedChunk {
+    /// `None` is air
+    palette: Vec<Option<StateKey>>,
+    states: Vec<u32>,
+    block_entities: HashMap<u32, BlockEntity>,
+    scheduled: Vec<SavedTick>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedTick {
+    delay: u64,
+    priority: i32,
+    index: u32,
+    /// Index into the palette
+    state: u32,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Self {
+            states: vec![StateId::AIR; CHUNK_VOLUME],
+            block_entities: HashMap::new(),
+            removed_block_entities: vec![],
+            scheduled: ScheduledTicks::new(),
+        }
+    }
+
+    pub fn get(&self, local: IVec3) -> StateId {
+        self.states[linearize(local)]
+    }
+
+    pub fn set(&mut self, local: IVec3, state: StateId) -> StateId {
+        std::mem::replace(&mut self.states[linearize(local)], state)
+    }
+
+    pub fn block_entity(&self, local: IVec3) -> Option<&BlockEntity> {
+        self.block_entities.get(&(linearize(local) as u32))
+    }
+
+    pub fn block_entity_mut(&mut self, local: IVec3) -> Option<&mut BlockEntity> {
+        self.block_entities.get_mut(&(linearize(local) as u32))
+    }
+
+    pub fn set_block_entity(&mut self, local: IVec3, entity: Option<BlockEntity>) -> Option<BlockEntity> {
+        let index = linearize(local) as u32;
+        match entity {
+            Some(entity) => {
+                self.removed_block_entities.retain(|removed| *removed != local);
+                self.block_entities.insert(index, entity)
+            }
+            None => {
+                let previous = self.block_entities.remove(&index);
+                if previous.is_some() {
+                    self.removed_block_entities.push(local);
+                }
+                previous
+            }
+        }
+    }
+
+    /// Removes a block entity without telling clients, for putting it back later
+    pub fn take_block_entity(&mut self, local: IVec3) -> Option<BlockEntity> {
+        self.block_entities.remove(&(linearize(local) as u32))
+    }
+
+    /// Tells clients about a removal that happened while the entity was taken out
+    pub(crate) fn mark_block_entity_removed(&mut self, local: IVec3) {
+        self.removed_block_entities.push(local);
+    }
+
+    /// Local positions of every block entity, in index order
+    pub fn block_entity_positions(&self) -> Vec<IVec3> {
+        let mut indices: Vec<u32> = self.block_entities.keys().copied().collect();
+        indices.sort_unstable();
+        indices.into_iter().map(|index| delinearize(index as usize)).collect()
+    }
+
+    pub fn scheduled(&self) -> &ScheduledTicks {
+        &self.scheduled
+    }
+
+    pub fn scheduled_mut(&mut self) -> &mut ScheduledTicks {
+        &mut self.scheduled
+    }
+
+    /// Atomically writes the chunk to `path`, keeping `backups` previous versions.
+    /// `time` is the current `Simulation::time`, scheduled ticks are saved relative to it
+    #[profiling::function]
+    pub fn save<T, B, I, D>(
+        &self,
+        path: impl AsRef<Path>,
+        registry: &Registry<T, B, I, D>,
+        time: u64,
+        backups: usize,
+    ) -> resources::Result<()>
+    where
+        T: Clone + Debug + Eq + Hash + PartialEq,
+        B: Block,
+        I: Item,
+    {
+        let mut palette = vec![];
+        let mut indices: HashMap<StateId, u32> = HashMap::new();
+        let mut index_of = |state: StateId| {
+            *indices.entry(state).or_insert_with(|| {
+                palette.push(registry.state_key(state));
+                (palette.len() - 1) as u32
+            })
+        };
+
+        let states = self.states.iter().map(|state| index_of(*state)).collect();
+        let scheduled = self
+            .scheduled
+            .ticks()
+            .into_iter()
+            .map(|tick| SavedTick {
+                delay: tick.due.saturating_sub(time),
+                priority: tick.priority,
+                index: linearize(tick.local_position()) as u32,
+                state: index_of(tick.state),
+            })
+            .collect();
+
+        let saved = SavedChunk {
+            palette,
+            states,
+            block_entities: self.block_entities.clone(),
+            scheduled,
+        };
+        let bytes = bincode::serialize(&saved).map_err(|e| ResourceError::parse(resources::resolve(&path), e))?;
+        resources::save_with_backups(path, &bytes, backups)
+    }
+
+    /// Reads a chunk written by `save`, mapping its blocks onto the current registry.
+    /// Blocks that are no longer registered turn into air
+    #[profiling::function]
+    pub fn load<T, B, I, D>(path: impl AsRef<Path>, registry: &Registry<T, B, I, D>, time: u64) -> resources::Result<Self>
+    where
+        T: Clone + Debug + Eq + Hash + PartialEq,
+        B: Block,
+        I: Item,
+    {
+        let path = resources::resolve(path);
+        let saved: SavedChunk = bincode::deserialize(&resources::read_bytes(&path)?)
+            .map_err(|e| ResourceError::parse(&path, e))?;
+        if saved.states.len() != CHUNK_VOLUME {
+            return Err(ResourceError::parse(path, "Chunk has the wrong number of states"));
+        }
+
+        let palette: Vec<StateId> = saved
+            .palette
+            .iter()
+            .map(|key| match key {
+                Some(key) => registry.state_from_key(key).unwrap_or_else(|| {
+                    log::warn!("{}: Block {} is not registered, replacing it with air", path.display(), key.block);
+                    StateId::AIR
+                }),
+                None => StateId::AIR,
+            })
+            .collect();
+        let state = |index: u32| {
+            palette
+                .get(index as usize)
+                .copied()
+                .ok_or_else(|| ResourceError::parse(&path, format!("State {index} is not in the chunk's palette")))
+        };
+
+        let mut chunk = Chunk::new();
+        for (local, index) in chunk.states.iter_mut().zip(saved.states) {
+            *local = state(index)?;
+        }
+        chunk.block_entities = saved.block_entities;
+        for tick in saved.scheduled {
+            if tick.index as usize >= CHUNK_VOLUME {
+                return Err(ResourceError::parse(&path, "Scheduled tick is outside of the chunk"));
+            }
+            let due = time + tick.delay;
+            chunk.scheduled.schedule(delinearize(tick.index as usize), due, tick.priority, state(tick.state)?);
+        }
+        Ok(chunk)
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The chunk index matching a local position
+pub fn linearize(local: IVec3) -> usize {
+    (local.x + local.y * CHUNK_SIZE + local.z * CHUNK_SIZE * CHUNK_SIZE) as usize
+}
+
+/// The local position matching a chunk index
+pub fn delinearize(index: usize) -> IVec3 {
+    let index = index as i32;
+    IVec3::new(
+        index % CHUNK_SIZE,
+        index / CHUNK_SIZE % CHUNK_SIZE,
+        index / (CHUNK_SIZE * CHUNK_SIZE),
+    )
+}
+
+pub fn chunk_position(position: IVec3) -> IVec3 {
+    IVec3::new(
+        position.x.div_euclid(CHUNK_SIZE),
+        position.y.div_euclid(CHUNK_SIZE),
+        position.z.div_euclid(CHUNK_SIZE),
+    )
+}
+
+pub fn local_position(position: IVec3) -> IVec3 {
+    IVec3::new(
+        position.x.rem_euclid(CHUNK_SIZE),
+        position.y.rem_euclid(CHUNK_SIZE),
+        position.z.rem_euclid(CHUNK_SIZE),
+    )
+}
+
+/// Every loaded chunk, keyed by chunk position
+#[derive(Default)]
+pub struct Terrain {
+    chunks: HashMap<IVec3, Chunk>,
+}
+
+impl Terrain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(&mut self, position: IVec3, chunk: Chunk) {
+        self.chunks.insert(position, chunk);
+    }
+
+    pub fn unload(&mut self, position: IVec3) -> Option<Chunk> {
+        self.chunks.remove(&position)
+    }
+
+    /// Loads a chunk previously written by `save_chunk`, returns whether a saved chunk existed
+    pub fn load_chunk<T, B, I, D>(
+        &mut self,
+        dir: impl AsRef<Path>,
+        position: IVec3,
+        registry: &Registry<T, B, I, D>,
+        time: u64,
+    ) -> resources::Result<bool>
+    where
+        T: Clone + Debug + Eq + Hash + PartialEq,
+        B: Block,
+        I: Item,
+    {
+        match Chunk::load(chunk_path(dir, position), registry, time) {
+            Ok(chunk) => {
+                self.load(position, chunk);
+                Ok(true)
+            }
+            Err(ResourceError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes a loaded chunk into `dir` keeping `backups` previous versions, does nothing if it is not loaded
+    pub fn save_chunk<T, B, I, D>(
+        &self,
+        dir: impl AsRef<Path>,
+        position: IVec3,
+        registry: &Registry<T, B, I, D>,
+        time: u64,
+        backups: usize,
+    ) -> resources::Result<()>
+    where
+        T: Clone + Debug + Eq + Hash + PartialEq,
+        B: Block,
+        I: Item,
+    {
+        let Some(chunk) = self.chunk(position) else { return Ok(()) };
+        chunk.save(chunk_path(dir, position), registry, time, backups)
+    }
+
+    pub fn chunk(&self, position: IVec3) -> Option<&Chunk> {
+        self.chunks.get(&position)
+    }
+
+    pub fn chunk_mut(&mut self, position: IVec3) -> Option<&mut Chunk> {
+        self.chunks.get_mut(&position)
+    }
+
+    /// Packets for every block entity whose synced fields changed since the last call, and for removed ones.
+    /// Entities whose fields can't be serialized are logged and skipped
+    #[profiling::function]
+    pub fn sync_block_entities(&mut self) -> Vec<BlockEntityPacket> {
+        let mut packets = vec![];
+        for position in self.chunk_positions() {
+            let Some(chunk) = self.chunks.get_mut(&position) else { continue };
+            for local in chunk.block_entity_positions() {
+                let Some(entity) = chunk.block_entity_mut(local) else { continue };
+                if !entity.take_dirty() {
+                    continue;
+                }
+                match BlockEntityPacket::new(position * CHUNK_SIZE + local, Some(entity)) {
+                    Ok(packet) => packets.push(packet),
+                    Err(e) => log::error!("Failed to sync block entity at {}: {e}", position * CHUNK_SIZE + local),
+                }
+            }
+            for local in chunk.removed_block_entities.drain(..) {
+                packets.push(BlockEntityPacket {
+                    position: position * CHUNK_SIZE + local,
+                    data: None,
+                });
+            }
+        }
+        packets
+    }
+
+    /// Loaded chunk positions in a stable order
+    pub fn chunk_positions(&self) -> Vec<IVec3> {
+        let mut positions: Vec<IVec3> = self.chunks.keys().copied().collect();
+        positions.sort_by_key(|position| (position.x, position.y, position.z));
+        positions
+    }
+}
+
+fn chunk_path(dir: impl AsRef<Path>, position: IVec3) -> PathBuf {
+    dir.as_ref().join(format!("{}_{}_{}.chunk", position.x, position.y, position.z))
+}
+
+impl WorldAccess for Terrain {
+    fn get_state(&self, position: IVec3) -> StateId {
+        self.chunk(chunk_position(position))
+            .map_or(StateId::AIR, |chunk| chunk.get(local_position(position)))
+    }
+
+    fn set_state(&mut self, position: IVec3, state: StateId) -> StateId {
+        match self.chunk_mut(chunk_position(position)) {
+            Some(chunk) => chunk.set(local_position(position), state),
+            None => StateId::AIR,
+        }
+    }
+
+    fn is_loaded(&self, position: IVec3) -> bool {
+        self.chunks.contains_key(&chunk_position(position))
+    }
+
+    fn block_entity(&self, position: IVec3) -> Option<&BlockEntity> {
+        self.chunk(chunk_position(position))?.block_entity(local_position(position))
+    }
+
+    fn block_entity_mut(&mut self, position: IVec3) -> Option<&mut BlockEntity> {
+        self.chunk_mut(chunk_position(position))?.block_entity_mut(local_position(position))
+    }
+
+    fn set_block_entity(&mut self, position: IVec3, entity: Option<BlockEntity>) -> Option<BlockEntity> {
+        self.chunk_mut(chunk_position(position))?.set_block_entity(local_position(position), entity)
+    }
+}
@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use shared::resources::{self, ResourceError, Versioned};
+
+/// Where the world is saved, its chunks and `world.toml`
+pub const WORLD_DIRECTORY: &str = "world";
+
+/// What a saved world keeps besides its chunks, in `world.toml`
+#[derive(Serialize, Deserialize)]
+pub struct WorldInfo {
+    /// Picked from the config when the world is created and kept afterwards, so changing the config doesn't reseed it
+    pub seed: u64,
+    /// `Simulation::time` when the world was saved, it resumes from here
+    pub time: u64,
+}
+
+impl WorldInfo {
+    /// Loads the world info in `dir`, creating a new world with `seed` if there is none yet
+    pub fn load_or_create(dir: impl AsRef<Path>, seed: u64) -> resources::Result<Self> {
+        let path = dir.as_ref().join("world.toml");
+        match resources::load_versioned(&path) {
+            Err(ResourceError::NotFound(_)) => {
+                let info = Self { seed, time: 0 };
+                resources::save_versioned(path, &info, 0)?;
+                Ok(info)
+            }
+            result => result,
+        }
+    }
+
+    pub fn save(&self, dir: impl AsRef<Path>, backups: usize) -> resources::Result<()> {
+        resources::save_versioned(dir.as_ref().join("world.toml"), self, backups)
+    }
+}
+
+impl Versioned for WorldInfo {
+    const SCHEMA_VERSION: u32 = 1;
+}
@@ -1,55 +1,32 @@
-/*use std::sync::{Arc, Mutex};
-use std::sync::mpsc::{channel, Sender, Receiver};
+use std::sync::mpsc::{channel, Receiver, Sender};
 
-struct Broadcast<T> {
+/// Fans a message out to every registered receiver
+pub struct Broadcast<T> {
     senders: Vec<Sender<T>>,
 }
 
 impl<T: Clone> Broadcast<T> {
-
-fn new() -> Self {
+    pub fn new() -> Self {
         Broadcast {
             senders: Vec::new(),
         }
     }
 
-fn register(&mut self) -> Receiver<T> {
+    pub fn register(&mut self) -> Receiver<T> {
         let (sender, receiver) = channel();
         self.senders.push(sender);
         receiver
     }
 
-fn broadcast(&self, msg: T) {
-        for sender in &self.senders {
-            let _ = sender.send(msg.clone());
-        }
+    /// Receivers that have been dropped are forgotten
+    #[profiling::function]
+    pub fn broadcast(&mut self, msg: T) {
+        self.senders.retain(|sender| sender.send(msg.clone()).is_ok());
     }
 }
 
-fn main() {
-    let broadcast = Arc::new(Mutex::new(Broadcast::new()));
-
-    let mut threads = Vec::new();
-
-    for i in 0..10 {
-        let broadcast_clone = Arc::clone(&broadcast);
-        let handle = std::thread::spawn(move || {
-            let receiver = broadcast_clone.lock().unwrap().register();
-            loop {
-                let msg = receiver.recv().unwrap();
-                println!("Thread {} received message: {:?}", i, msg);
-            }
-        });
-        threads.push(handle);
+impl<T: Clone> Default for Broadcast<T> {
+    fn default() -> Self {
+        Self::new()
     }
-
-    // simulate broadcasting messages
-    for i in 0..10 {
-        broadcast.lock().unwrap().broadcast(format!("message {}", i));
-    }
-
-    // wait for all threads to finish
-    for handle in threads {
-        handle.join().unwrap();
-    }
-}*/
+}
@@ -1,27 +1,48 @@
-use glam::IVec3;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+use glam::{IVec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{context::States, state::{StateId, Value}};
+use crate::util::BoundingBox;
+
+/// One of the six faces of a block. Ids follow the variant order
+///
+/// `WEST` points towards +X and `EAST` towards -X
+#[repr(u8)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Direction {
     UP,
     DOWN,
     NORTH,
-    WEST,
     SOUTH,
+    WEST,
     EAST
 }
 
 impl Direction {
-    pub fn get(id: u8) -> Direction {
-        match id {
-            0 => Direction::UP,
-            1 => Direction::DOWN,
-            2 => Direction::NORTH,
-            3 => Direction::SOUTH,
-            4 => Direction::WEST,
-            5 => Direction::EAST,
-            _ => panic!("Invalid ID!"),
-        }
+    pub const ALL: [Direction; 6] = [
+        Direction::UP,
+        Direction::DOWN,
+        Direction::NORTH,
+        Direction::SOUTH,
+        Direction::WEST,
+        Direction::EAST,
+    ];
+
+    /// Clockwise when seen from above, starting at north
+    pub const HORIZONTAL: [Direction; 4] = [Direction::NORTH, Direction::WEST, Direction::SOUTH, Direction::EAST];
+
+    pub fn get(id: u8) -> Option<Direction> {
+        Self::ALL.get(usize::from(id)).copied()
     }
+
+    pub fn iter() -> impl Iterator<Item = Direction> {
+        Self::ALL.into_iter()
+    }
+
     pub fn get_normal(&self) -> IVec3 {
         match self {
             Direction::UP => IVec3::new(0, 1, 0),
@@ -32,24 +53,196 @@ impl Direction {
             Direction::EAST => IVec3::new(-1, 0, 0),
         }
     }
+
+    /// The direction a unit axis vector points in
+    pub fn from_normal(normal: IVec3) -> Option<Direction> {
+        Self::iter().find(|direction| direction.get_normal() == normal)
+    }
+
     pub fn get_id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn get_string(&self) -> String {
+        self.name().to_string()
+    }
+
+    pub fn name(&self) -> &'static str {
         match self {
-            Direction::UP => 0,
-            Direction::DOWN => 1,
-            Direction::NORTH => 2,
-            Direction::SOUTH => 3,
-            Direction::WEST => 4,
-            Direction::EAST => 5,
+            Direction::UP => "up",
+            Direction::DOWN => "down",
+            Direction::NORTH => "north",
+            Direction::SOUTH => "south",
+            Direction::WEST => "west",
+            Direction::EAST => "east",
         }
     }
-    pub fn get_string(&self) -> String {
+
+    pub fn opposite(&self) -> Direction {
         match self {
-            Direction::UP => String::from("up"),
-            Direction::DOWN => String::from("down"),
-            Direction::NORTH => String::from("north"),
-            Direction::SOUTH => String::from("south"),
-            Direction::WEST => String::from("west"),
-            Direction::EAST => String::from("east"),
+            Direction::UP => Direction::DOWN,
+            Direction::DOWN => Direction::UP,
+            Direction::NORTH => Direction::SOUTH,
+            Direction::SOUTH => Direction::NORTH,
+            Direction::WEST => Direction::EAST,
+            Direction::EAST => Direction::WEST,
+        }
+    }
+
+    pub fn axis(&self) -> Axis {
+        match self {
+            Direction::WEST | Direction::EAST => Axis::X,
+            Direction::UP | Direction::DOWN => Axis::Y,
+            Direction::NORTH | Direction::SOUTH => Axis::Z,
+        }
+    }
+
+    /// Whether the normal points along the positive end of its axis
+    pub fn is_positive(&self) -> bool {
+        matches!(self, Direction::UP | Direction::SOUTH | Direction::WEST)
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        self.axis() != Axis::Y
+    }
+
+    /// Turns by `turns` quarter turns around the vertical axis, clockwise when seen from above. Up and down stay put
+    pub fn rotate_y(&self, turns: i32) -> Direction {
+        let Some(index) = Self::HORIZONTAL.iter().position(|direction| direction == self) else { return *self };
+        Self::HORIZONTAL[(index as i32 + turns).rem_euclid(4) as usize]
+    }
+
+    /// Flips directions along `axis`, leaving the others alone
+    pub fn mirror(&self, axis: Axis) -> Direction {
+        if self.axis() == axis {
+            self.opposite()
+        } else {
+            *self
+        }
+    }
+}
+
+impl TryFrom<u8> for Direction {
+    type Error = InvalidDirection;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Self::get(id).ok_or(InvalidDirection(id.to_string()))
+    }
+}
+
+impl FromStr for Direction {
+    type Err = InvalidDirection;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::iter()
+            .find(|direction| direction.name() == s)
+            .ok_or_else(|| InvalidDirection(s.to_string()))
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidDirection(pub String);
+
+impl fmt::Display for InvalidDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\" is not a direction", self.0)
+    }
+}
+
+impl std::error::Error for InvalidDirection {}
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+    /// The direction pointing along this axis, towards its positive or negative end
+    pub fn direction(&self, positive: bool) -> Direction {
+        match (self, positive) {
+            (Axis::X, true) => Direction::WEST,
+            (Axis::X, false) => Direction::EAST,
+            (Axis::Y, true) => Direction::UP,
+            (Axis::Y, false) => Direction::DOWN,
+            (Axis::Z, true) => Direction::SOUTH,
+            (Axis::Z, false) => Direction::NORTH,
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// How a block is placed relative to its default orientation: mirrored first, then turned around the vertical axis.
+/// Applies the same way to directions, block local positions, shapes and directional block state properties
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct BlockTransform {
+    /// Quarter turns clockwise when seen from above
+    pub turns: u8,
+    pub mirror: Option<Axis>,
+}
+
+impl BlockTransform {
+    pub const IDENTITY: BlockTransform = BlockTransform { turns: 0, mirror: None };
+
+    pub fn rotation(turns: i32) -> Self {
+        Self {
+            turns: turns.rem_euclid(4) as u8,
+            mirror: None,
+        }
+    }
+
+    /// Turns a north facing block to face `direction`, vertical directions leave it as is
+    pub fn facing(direction: Direction) -> Self {
+        let turns = Direction::HORIZONTAL.iter().position(|other| *other == direction).unwrap_or(0);
+        Self::rotation(turns as i32)
+    }
+
+    pub fn mirrored(mut self, axis: Axis) -> Self {
+        self.mirror = Some(axis);
+        self
+    }
+
+    pub fn apply(&self, direction: Direction) -> Direction {
+        let direction = match self.mirror {
+            Some(axis) => direction.mirror(axis),
+            None => direction,
+        };
+        direction.rotate_y(i32::from(self.turns))
+    }
+
+    /// Transforms a position in block local coordinates around the block's center
+    pub fn apply_point(&self, point: Vec3) -> Vec3 {
+        let mut point = point - Vec3::splat(0.5);
+        if let Some(axis) = self.mirror {
+            point[axis.index()] = -point[axis.index()];
+        }
+        for _ in 0..self.turns % 4 {
+            point = Vec3::new(-point.z, point.y, point.x);
         }
+        point + Vec3::splat(0.5)
     }
-}
\ No newline at end of file
+
+    pub fn apply_box(&self, bounds: &BoundingBox) -> BoundingBox {
+        BoundingBox::new(self.apply_point(bounds.0), self.apply_point(bounds.1))
+    }
+
+    /// Transforms a property holding a direction name, such as `facing`. `None` if the state has no such property
+    pub fn apply_state(&self, states: &dyn States, state: StateId, property: &str) -> Option<StateId> {
+        let Some(Value::Enum(value)) = states.get_property(state, property) else { return None };
+        let direction = self.apply(value.parse().ok()?);
+        states.with_property(state, property, &Value::enumeration(direction.name()))
+    }
+}
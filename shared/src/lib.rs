@@ -1,12 +1,17 @@
 use std::sync::mpsc::{Receiver, Sender};
 
 pub use log;
+pub use rand;
+pub use toml;
 pub mod network {
     pub use uflow::*;
 }
 pub mod math {
     pub use glam::*;
 }
+pub mod lua {
+    pub use mlua::*;
+}
 
 pub mod broadcast;
 pub mod direction;
@@ -14,6 +19,7 @@ pub mod model;
 pub mod packets;
 pub mod registry;
 pub mod resources;
+pub mod shape;
 pub mod types;
 pub mod util;
 
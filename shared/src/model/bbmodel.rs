@@ -0,0 +1,372 @@
+use std::collections::{BTreeMap, HashMap};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use glam::{EulerRot, Quat, Vec3};
+use serde::Deserialize;
+
+use crate::direction::Direction;
+
+use super::{box_face, face_uvs, parse_face, quad_normal, BakedModel, BakedQuad, ModelError, ModelTexture, PIXELS};
+
+#[derive(Deserialize)]
+struct BbModel {
+    #[serde(default)]
+    meta: Meta,
+    #[serde(default)]
+    resolution: Resolution,
+    #[serde(default)]
+    elements: Vec<Element>,
+    #[serde(default)]
+    outliner: Vec<OutlinerNode>,
+    #[serde(default)]
+    textures: Vec<Texture>,
+}
+
+#[derive(Default, Deserialize)]
+struct Meta {
+    #[serde(default)]
+    model_format: String,
+    #[serde(default)]
+    box_uv: bool,
+}
+
+#[derive(Deserialize)]
+struct Resolution {
+    width: f32,
+    height: f32,
+}
+
+impl Default for Resolution {
+    fn default() -> Self {
+        Self { width: PIXELS, height: PIXELS }
+    }
+}
+
+#[derive(Deserialize)]
+struct Element {
+    #[serde(default = "cube", rename = "type")]
+    kind: String,
+    #[serde(default)]
+    uuid: String,
+    from: [f32; 3],
+    to: [f32; 3],
+    #[serde(default)]
+    origin: [f32; 3],
+    #[serde(default)]
+    rotation: [f32; 3],
+    #[serde(default)]
+    inflate: f32,
+    #[serde(default = "yes")]
+    export: bool,
+    box_uv: Option<bool>,
+    #[serde(default)]
+    uv_offset: [f32; 2],
+    #[serde(default)]
+    faces: BTreeMap<String, Face>,
+}
+
+#[derive(Deserialize)]
+struct Face {
+    uv: Option<[f32; 4]>,
+    /// An index into the texture list, `null` for faces without a texture
+    texture: Option<serde_json::Value>,
+    #[serde(default)]
+    rotation: f32,
+    cullface: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OutlinerNode {
+    Element(String),
+    Group(Group),
+}
+
+#[derive(Deserialize)]
+struct Group {
+    #[serde(default)]
+    origin: [f32; 3],
+    #[serde(default)]
+    rotation: [f32; 3],
+    #[serde(default = "yes")]
+    export: bool,
+    #[serde(default)]
+    children: Vec<OutlinerNode>,
+}
+
+#[derive(Deserialize)]
+struct Texture {
+    #[serde(default)]
+    name: String,
+    path: Option<String>,
+    /// A `data:` URL for embedded images
+    source: Option<String>,
+    uv_width: Option<f32>,
+    uv_height: Option<f32>,
+}
+
+fn cube() -> String {
+    "cube".to_string()
+}
+
+fn yes() -> bool {
+    true
+}
+
+/// A rotation in degrees around a pivot, in Blockbench units
+#[derive(Clone, Copy)]
+struct Pivot {
+    origin: Vec3,
+    rotation: Quat,
+}
+
+impl Pivot {
+    fn new(origin: [f32; 3], rotation: [f32; 3]) -> Self {
+        let [x, y, z] = rotation.map(f32::to_radians);
+        Self {
+            origin: Vec3::from(origin),
+            rotation: Quat::from_euler(EulerRot::ZYX, z, y, x),
+        }
+    }
+
+    fn apply(&self, point: Vec3) -> Vec3 {
+        self.rotation * (point - self.origin) + self.origin
+    }
+}
+
+/// Bakes a Blockbench `.bbmodel` file
+///
+/// Cubes are placed by their own rotation and then by the rotations of the groups containing them, innermost first.
+/// Elements and groups with export turned off are skipped, as are faces without a texture. `java_block` models
+/// already span 0 to 16, other formats are centred on the origin and get shifted half a block along x and z
+#[profiling::function]
+pub fn parse(json: &str) -> Result<BakedModel, ModelError> {
+    let model: BbModel = serde_json::from_str(json)?;
+
+    let offset = if model.meta.model_format == "java_block" {
+        Vec3::ZERO
+    } else {
+        Vec3::new(PIXELS / 2.0, 0.0, PIXELS / 2.0)
+    };
+
+    let mut pivots = HashMap::new();
+    walk_outliner(&model.outliner, &mut vec![], &mut pivots);
+
+    let mut quads = vec![];
+    for element in &model.elements {
+        if element.kind != "cube" || !element.export {
+            continue;
+        }
+        let groups = match pivots.get(element.uuid.as_str()) {
+            Some(Some(groups)) => groups.as_slice(),
+            Some(None) => continue,
+            None => &[],
+        };
+        bake_element(&model, element, groups, offset, &mut quads)?;
+    }
+
+    let textures = model.textures.iter().map(bake_texture).collect::<Result<_, _>>()?;
+
+    Ok(BakedModel { quads, textures })
+}
+
+/// Maps element uuids to the pivots of their groups from the outside in, `None` for elements in non-exported groups
+fn walk_outliner<'a>(
+    nodes: &'a [OutlinerNode],
+    parents: &mut Vec<Option<Pivot>>,
+    pivots: &mut HashMap<&'a str, Option<Vec<Pivot>>>,
+) {
+    for node in nodes {
+        match node {
+            OutlinerNode::Element(uuid) => {
+                pivots.insert(uuid, parents.iter().copied().collect());
+            }
+            OutlinerNode::Group(group) => {
+                parents.push(group.export.then(|| Pivot::new(group.origin, group.rotation)));
+                walk_outliner(&group.children, parents, pivots);
+                parents.pop();
+            }
+        }
+    }
+}
+
+fn bake_element(
+    model: &BbModel,
+    element: &Element,
+    groups: &[Pivot],
+    offset: Vec3,
+    quads: &mut Vec<BakedQuad>,
+) -> Result<(), ModelError> {
+    let inflate = Vec3::splat(element.inflate);
+    let from = Vec3::from(element.from) - inflate;
+    let to = Vec3::from(element.to) + inflate;
+    let pivot = Pivot::new(element.origin, element.rotation);
+    let box_uv = element.box_uv.unwrap_or(model.meta.box_uv);
+
+    for (name, face) in &element.faces {
+        let direction = parse_face(name)?;
+
+        let Some(texture) = face.texture.as_ref().and_then(texture_index) else {
+            continue;
+        };
+        let Some(texture_info) = model.textures.get(texture) else {
+            return Err(ModelError::InvalidTexture(format!("Face {name} uses missing texture {texture}")));
+        };
+
+        let uv = match face.uv {
+            Some(uv) => uv,
+            None if box_uv => box_uv_rect(element, direction),
+            None => continue,
+        };
+        let width = texture_info.uv_width.unwrap_or(model.resolution.width);
+        let height = texture_info.uv_height.unwrap_or(model.resolution.height);
+        let uvs = face_uvs([uv[0] / width, uv[1] / height, uv[2] / width, uv[3] / height], face.rotation)?;
+
+        let positions = box_face(from, to, direction).map(|corner| {
+            let corner = groups.iter().rev().fold(pivot.apply(corner), |corner, group| group.apply(corner));
+            (corner + offset) / PIXELS
+        });
+        let Some(normal) = quad_normal(&positions) else {
+            continue;
+        };
+
+        let cull_face = match &face.cullface {
+            Some(cull_face) => Some(parse_face(cull_face)?),
+            None => None,
+        };
+
+        quads.push(BakedQuad { positions, uvs, normal, texture, cull_face });
+    }
+
+    Ok(())
+}
+
+fn texture_index(value: &serde_json::Value) -> Option<usize> {
+    match value {
+        serde_json::Value::Number(number) => number.as_u64().map(|index| index as usize),
+        serde_json::Value::String(index) => index.parse().ok(),
+        _ => None,
+    }
+}
+
+/// The face rectangle of Blockbench's box UV layout, unfolded from `uv_offset`
+///
+/// Blockbench unfolds its east (+X) face first, which is `Direction::WEST` here
+fn box_uv_rect(element: &Element, face: Direction) -> [f32; 4] {
+    let [u, v] = element.uv_offset;
+    let size = (Vec3::from(element.to) - Vec3::from(element.from)).abs();
+    let (dx, dy, dz) = (size.x.floor(), size.y.floor(), size.z.floor());
+    match face {
+        Direction::UP => [u + dz + dx, v + dz, u + dz, v],
+        Direction::DOWN => [u + dz + dx * 2.0, v, u + dz + dx, v + dz],
+        Direction::WEST => [u, v + dz, u + dz, v + dz + dy],
+        Direction::NORTH => [u + dz, v + dz, u + dz + dx, v + dz + dy],
+        Direction::EAST => [u + dz + dx, v + dz, u + dz * 2.0 + dx, v + dz + dy],
+        Direction::SOUTH => [u + dz * 2.0 + dx, v + dz, u + (dz + dx) * 2.0, v + dz + dy],
+    }
+}
+
+fn bake_texture(texture: &Texture) -> Result<ModelTexture, ModelError> {
+    let data = match texture.source.as_deref().and_then(|source| source.strip_prefix("data:")) {
+        Some(url) => {
+            let Some((_, encoded)) = url.split_once(";base64,") else {
+                return Err(ModelError::InvalidTexture(format!("Texture {} is not base64 encoded", texture.name)));
+            };
+            let data = STANDARD
+                .decode(encoded)
+                .map_err(|e| ModelError::InvalidTexture(format!("Texture {}: {e}", texture.name)))?;
+            Some(data)
+        }
+        None => None,
+    };
+
+    Ok(ModelTexture {
+        name: texture.name.clone(),
+        data,
+        path: texture.path.clone().filter(|path| !path.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use glam::{Vec2, Vec3};
+
+    use super::*;
+
+    fn sample(name: &str) -> BakedModel {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/models").join(name);
+        parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn quad(model: &BakedModel, normal: Vec3) -> &BakedQuad {
+        model.quads.iter().find(|quad| quad.normal.abs_diff_eq(normal, 1e-4)).unwrap()
+    }
+
+    #[test]
+    fn slab() {
+        let model = sample("slab.bbmodel");
+        assert_eq!(model.quads.len(), 6);
+
+        let up = quad(&model, Vec3::Y);
+        assert!(up.positions.iter().all(|position| position.y == 0.5));
+        assert_eq!(up.cull_face, None);
+        assert_eq!(up.uvs, [Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 0.0)]);
+
+        let north = quad(&model, Vec3::NEG_Z);
+        assert_eq!(north.cull_face, Some(Direction::NORTH));
+        assert_eq!(north.positions[0], Vec3::new(1.0, 0.5, 0.0));
+        assert_eq!(north.uvs[0], Vec2::new(0.0, 0.5));
+        assert_eq!(north.uvs[2], Vec2::new(1.0, 1.0));
+
+        assert_eq!(quad(&model, Vec3::X).cull_face, Some(Direction::WEST));
+        assert_eq!(quad(&model, Vec3::NEG_Y).cull_face, Some(Direction::DOWN));
+
+        let texture = &model.textures[0];
+        assert_eq!(texture.name, "stone.png");
+        assert_eq!(texture.path, None);
+        assert!(texture.data.as_ref().unwrap().starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn lamp() {
+        let model = sample("lamp.bbmodel");
+        // Post, one textured shade face and the top of the flat plane
+        assert_eq!(model.quads.len(), 8);
+        assert_eq!(model.textures[0].path.as_deref(), Some("textures/lamp.png"));
+        assert_eq!(model.textures[0].data, None);
+
+        // The root group lays the post on its side, so its top now faces west
+        let top = quad(&model, Vec3::NEG_X);
+        assert!(top.positions.iter().all(|position| (position.x + 0.25).abs() < 1e-5));
+
+        // Box UV, 2x12x2 cube at offset 0 on a 32x32 texture
+        let north = quad(&model, Vec3::NEG_Z);
+        assert_eq!(north.uvs[0], Vec2::new(2.0, 2.0) / 32.0);
+        assert_eq!(north.uvs[2], Vec2::new(4.0, 14.0) / 32.0);
+
+        // Turned 45 degrees by the shade and then 90 by the root group
+        let shade = Vec3::new(0.0, -1.0, -1.0).normalize();
+        assert!(model.quads.iter().any(|quad| quad.normal.abs_diff_eq(shade, 1e-4)));
+
+        // The flat plane keeps its top but drops the side without area
+        let plane = model.quads.iter().find(|quad| quad.positions.iter().all(|position| position.y == 0.0)).unwrap();
+        assert_eq!(plane.normal, Vec3::Y);
+        assert_eq!(plane.positions[0], Vec3::new(6.0, 0.0, 6.0) / 16.0);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(parse("{"), Err(ModelError::Json(_))));
+
+        let missing = r#"{"elements": [{"from": [0, 0, 0], "to": [1, 1, 1], "faces": {"up": {"uv": [0, 0, 1, 1], "texture": 3}}}]}"#;
+        assert!(matches!(parse(missing), Err(ModelError::InvalidTexture(_))));
+
+        let face = r#"{"elements": [{"from": [0, 0, 0], "to": [1, 1, 1], "faces": {"top": {"texture": 0}}}], "textures": [{}]}"#;
+        assert!(matches!(parse(face), Err(ModelError::InvalidFace(_))));
+
+        let rotation = r#"{"elements": [{"from": [0, 0, 0], "to": [1, 1, 1], "faces": {"up": {"uv": [0, 0, 1, 1], "texture": 0, "rotation": 45}}}], "textures": [{}]}"#;
+        assert!(matches!(parse(rotation), Err(ModelError::InvalidRotation(_))));
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use glam::{Quat, Vec3};
+use serde::Deserialize;
+
+use crate::{
+    direction::Direction,
+    resources::{ResourceError, Vfs},
+    types::Key,
+};
+
+use super::{box_face, face_uvs, parse_face, quad_normal, BakedModel, BakedQuad, ModelError, ModelTexture, PIXELS};
+
+/// How many `#variable` references a texture lookup follows before giving up
+const MAX_TEXTURE_DEPTH: usize = 16;
+
+#[derive(Deserialize)]
+struct ModelDefinition {
+    parent: Option<String>,
+    #[serde(default)]
+    textures: BTreeMap<String, String>,
+    elements: Option<Vec<Element>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Element {
+    from: [f32; 3],
+    to: [f32; 3],
+    rotation: Option<Rotation>,
+    #[serde(default)]
+    faces: BTreeMap<String, Face>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Rotation {
+    #[serde(default = "centre")]
+    origin: [f32; 3],
+    axis: RotationAxis,
+    angle: f32,
+    /// Stretches the rotated faces so they still span the whole block
+    #[serde(default)]
+    rescale: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RotationAxis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Face {
+    uv: Option<[f32; 4]>,
+    texture: String,
+    cullface: Option<String>,
+    #[serde(default)]
+    rotation: f32,
+}
+
+fn centre() -> [f32; 3] {
+    [PIXELS / 2.0; 3]
+}
+
+/// A JSON model with its parent chain merged in
+///
+/// Texture references are qualified with the namespace of the model that set them, `#variable` references are kept
+#[derive(Clone, Debug, Default)]
+pub struct ResolvedModel {
+    textures: BTreeMap<String, String>,
+    elements: Vec<Element>,
+}
+
+impl ResolvedModel {
+    /// Follows `#variable` references until reaching a texture key
+    pub fn texture<'a>(&'a self, reference: &'a str) -> Result<&'a str, ModelError> {
+        let mut current = reference;
+        for _ in 0..MAX_TEXTURE_DEPTH {
+            let Some(variable) = current.strip_prefix('#') else {
+                return Ok(current);
+            };
+            current = self.textures.get(variable).ok_or_else(|| {
+                ModelError::InvalidTexture(format!("Texture variable #{variable} is not defined"))
+            })?;
+        }
+        Err(ModelError::InvalidTexture(format!("Texture variable {reference} refers back to itself")))
+    }
+
+    pub fn textures(&self) -> &BTreeMap<String, String> {
+        &self.textures
+    }
+
+    #[profiling::function]
+    pub fn bake(&self) -> Result<BakedModel, ModelError> {
+        let mut baked = BakedModel::default();
+        let mut slots: HashMap<String, usize> = HashMap::new();
+
+        for element in &self.elements {
+            let from = Vec3::from(element.from);
+            let to = Vec3::from(element.to);
+
+            for (name, face) in &element.faces {
+                let direction = parse_face(name)?;
+
+                let uv = face.uv.unwrap_or_else(|| default_uv(from, to, direction)).map(|uv| uv / PIXELS);
+                let uvs = face_uvs(uv, face.rotation)?;
+
+                let positions = box_face(from, to, direction).map(|corner| {
+                    let corner = match &element.rotation {
+                        Some(rotation) => rotate(corner, rotation),
+                        None => corner,
+                    };
+                    corner / PIXELS
+                });
+                let Some(normal) = quad_normal(&positions) else {
+                    continue;
+                };
+
+                let cull_face = match &face.cullface {
+                    Some(cull_face) => Some(parse_face(cull_face)?),
+                    None => None,
+                };
+
+                let texture = self.texture(&face.texture)?;
+                let texture = match slots.get(texture) {
+                    Some(slot) => *slot,
+                    None => {
+                        let key = Key::parse(texture)?;
+                        baked.textures.push(ModelTexture {
+                            name: texture.to_string(),
+                            data: None,
+                            path: Some(format!("{}:textures/{}.png", key.namespace, key.path)),
+                        });
+                        slots.insert(texture.to_string(), baked.textures.len() - 1);
+                        baked.textures.len() - 1
+                    }
+                };
+
+                baked.quads.push(BakedQuad { positions, uvs, normal, texture, cull_face });
+            }
+        }
+
+        Ok(baked)
+    }
+}
+
+/// Resolves and caches JSON block models stored at `<namespace>:models/<path>.json`
+///
+/// Models may name a `parent`, whose textures they extend and whose elements they inherit unless they define their own.
+/// Every model in a chain is cached, so shared parents are only read once
+#[derive(Default)]
+pub struct JsonModels {
+    resolved: HashMap<Key, Arc<ResolvedModel>>,
+}
+
+impl JsonModels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[profiling::function]
+    pub fn resolve(&mut self, vfs: &Vfs, model: &Key) -> Result<Arc<ResolvedModel>, ModelError> {
+        self.resolve_chain(vfs, model, &mut vec![])
+    }
+
+    #[profiling::function]
+    pub fn bake(&mut self, vfs: &Vfs, model: &Key) -> Result<BakedModel, ModelError> {
+        self.resolve(vfs, model)?.bake()
+    }
+
+    /// Forgets every resolved model, for example after resources were reloaded
+    pub fn clear(&mut self) {
+        self.resolved.clear();
+    }
+
+    fn resolve_chain(&mut self, vfs: &Vfs, model: &Key, chain: &mut Vec<Key>) -> Result<Arc<ResolvedModel>, ModelError> {
+        if let Some(resolved) = self.resolved.get(model) {
+            return Ok(resolved.clone());
+        }
+        if let Some(start) = chain.iter().position(|key| key == model) {
+            let mut cycle = chain[start..].to_vec();
+            cycle.push(model.clone());
+            return Err(ModelError::Cycle(cycle));
+        }
+
+        let path = format!("{}:models/{}.json", model.namespace, model.path);
+        let contents = match vfs.read_string(&path) {
+            Ok(contents) => contents,
+            Err(ResourceError::NotFound(_)) if !chain.is_empty() => {
+                return Err(ModelError::MissingParent {
+                    model: chain[chain.len() - 1].clone(),
+                    parent: model.clone(),
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let definition: ModelDefinition =
+            serde_json::from_str(&contents).map_err(|e| ResourceError::parse(&path, e))?;
+
+        let mut resolved = match &definition.parent {
+            Some(parent) => {
+                let parent = Key::parse_in(&model.namespace, parent)?;
+                chain.push(model.clone());
+                let parent = self.resolve_chain(vfs, &parent, chain);
+                chain.pop();
+                ResolvedModel::clone(&*parent?)
+            }
+            None => ResolvedModel::default(),
+        };
+
+        for (variable, texture) in definition.textures {
+            let texture = if texture.starts_with('#') {
+                texture
+            } else {
+                Key::parse_in(&model.namespace, &texture)?.to_string()
+            };
+            resolved.textures.insert(variable, texture);
+        }
+        if let Some(elements) = definition.elements {
+            resolved.elements = elements;
+        }
+
+        let resolved = Arc::new(resolved);
+        self.resolved.insert(model.clone(), resolved.clone());
+        Ok(resolved)
+    }
+}
+
+/// The part of the texture a face shows when it doesn't set `uv`, as if the texture were projected onto the box
+fn default_uv(from: Vec3, to: Vec3, face: Direction) -> [f32; 4] {
+    match face {
+        Direction::UP => [from.x, from.z, to.x, to.z],
+        Direction::DOWN => [from.x, PIXELS - to.z, to.x, PIXELS - from.z],
+        Direction::NORTH => [PIXELS - to.x, PIXELS - to.y, PIXELS - from.x, PIXELS - from.y],
+        Direction::SOUTH => [from.x, PIXELS - to.y, to.x, PIXELS - from.y],
+        Direction::EAST => [from.z, PIXELS - to.y, to.z, PIXELS - from.y],
+        Direction::WEST => [PIXELS - to.z, PIXELS - to.y, PIXELS - from.z, PIXELS - from.y],
+    }
+}
+
+fn rotate(point: Vec3, rotation: &Rotation) -> Vec3 {
+    let origin = Vec3::from(rotation.origin);
+    let angle = rotation.angle.to_radians();
+    let (quat, axis) = match rotation.axis {
+        RotationAxis::X => (Quat::from_rotation_x(angle), Vec3::X),
+        RotationAxis::Y => (Quat::from_rotation_y(angle), Vec3::Y),
+        RotationAxis::Z => (Quat::from_rotation_z(angle), Vec3::Z),
+    };
+
+    let mut offset = point - origin;
+    if rotation.rescale && angle.cos().abs() > f32::EPSILON {
+        let scale = Vec3::ONE + (Vec3::ONE - axis) * (1.0 / angle.cos().abs() - 1.0);
+        offset *= scale;
+    }
+    quat * offset + origin
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use crate::resources::test_dir::TempDir;
+
+    use super::*;
+
+    /// A fresh directory holding `files`, mounted as the `test` namespace. It's removed once the directory is dropped
+    fn mount(name: &str, files: &[(&str, &str)]) -> (Vfs, TempDir) {
+        let dir = TempDir::new(&format!("json_models_{name}"));
+        for (path, contents) in files {
+            dir.write(path, contents);
+        }
+
+        let mut vfs = Vfs::new();
+        vfs.mount_addon("test", &*dir).unwrap();
+        (vfs, dir)
+    }
+
+    fn key(key: &str) -> Key {
+        Key::parse(key).unwrap()
+    }
+
+    #[test]
+    fn parent_chain() {
+        let (vfs, _dir) = mount("chain", &[
+            (
+                "models/block/cube.json",
+                r##"{"elements": [{"from": [0, 0, 0], "to": [16, 16, 16], "faces": {
+                    "up": {"texture": "#top"}, "down": {"texture": "#bottom"}, "east": {"texture": "#side", "cullface": "east"}}}]}"##,
+            ),
+            ("models/block/column.json", r##"{"parent": "block/cube", "textures": {"top": "#end", "bottom": "#end"}}"##),
+            ("models/block/log.json", r#"{"parent": "block/column", "textures": {"end": "block/log_top", "side": "other:block/bark"}}"#),
+        ]);
+
+        let mut models = JsonModels::new();
+        let model = models.bake(&vfs, &key("test:block/log")).unwrap();
+
+        assert_eq!(model.quads.len(), 3);
+        let names: Vec<_> = model.textures.iter().map(|texture| texture.name.as_str()).collect();
+        assert_eq!(names, ["test:block/log_top", "other:block/bark"]);
+        assert_eq!(model.textures[1].path.as_deref(), Some("other:textures/block/bark.png"));
+
+        // Model files call +X east
+        let side = model.quads.iter().find(|quad| quad.normal == Vec3::X).unwrap();
+        assert_eq!(side.cull_face, Some(Direction::WEST));
+        assert_eq!(model.textures[side.texture].name, "other:block/bark");
+
+        // Every model in the chain was cached on the way
+        assert!(models.resolved.contains_key(&key("test:block/column")));
+        assert!(models.resolved.contains_key(&key("test:block/cube")));
+    }
+
+    #[test]
+    fn cycle() {
+        let (vfs, _dir) = mount("cycle", &[
+            ("models/block/a.json", r#"{"parent": "block/b"}"#),
+            ("models/block/b.json", r#"{"parent": "block/a"}"#),
+        ]);
+
+        let result = JsonModels::new().resolve(&vfs, &key("test:block/a"));
+
+        match result {
+            Err(ModelError::Cycle(cycle)) => {
+                assert_eq!(cycle, [key("test:block/a"), key("test:block/b"), key("test:block/a")]);
+            }
+            _ => panic!("Expected a cycle"),
+        }
+    }
+
+    #[test]
+    fn missing_parent() {
+        let (vfs, _dir) = mount("missing", &[("models/block/orphan.json", r#"{"parent": "block/nothing"}"#)]);
+
+        let mut models = JsonModels::new();
+        let orphan = models.resolve(&vfs, &key("test:block/orphan"));
+        let missing = models.resolve(&vfs, &key("test:block/none"));
+
+        match orphan {
+            Err(ModelError::MissingParent { model, parent }) => {
+                assert_eq!(model, key("test:block/orphan"));
+                assert_eq!(parent, key("test:block/nothing"));
+            }
+            _ => panic!("Expected a missing parent"),
+        }
+        assert!(matches!(missing, Err(ModelError::Resource(ResourceError::NotFound(_)))));
+    }
+
+    #[test]
+    fn default_uvs() {
+        let (from, to) = (Vec3::new(2.0, 0.0, 4.0), Vec3::new(6.0, 8.0, 10.0));
+        assert_eq!(default_uv(from, to, Direction::UP), [2.0, 4.0, 6.0, 10.0]);
+        assert_eq!(default_uv(from, to, Direction::DOWN), [2.0, 6.0, 6.0, 12.0]);
+        assert_eq!(default_uv(from, to, Direction::NORTH), [10.0, 8.0, 14.0, 16.0]);
+        assert_eq!(default_uv(from, to, Direction::SOUTH), [2.0, 8.0, 6.0, 16.0]);
+        assert_eq!(default_uv(from, to, Direction::EAST), [4.0, 8.0, 10.0, 16.0]);
+        assert_eq!(default_uv(from, to, Direction::WEST), [6.0, 8.0, 12.0, 16.0]);
+    }
+}
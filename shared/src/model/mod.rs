@@ -0,0 +1,177 @@
+use std::{fmt, path::PathBuf};
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    direction::Direction,
+    resources::{ResourceError, Vfs},
+    types::{Key, KeyError},
+};
+
+pub use self::json::{JsonModels, ResolvedModel};
+
+pub mod bbmodel;
+pub mod json;
+
+/// Model units per block, the same for JSON and Blockbench models
+pub(crate) const PIXELS: f32 = 16.0;
+
+/// A model flattened into textured quads in block space, where a full block spans 0 to 1 on every axis
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BakedModel {
+    pub quads: Vec<BakedQuad>,
+    pub textures: Vec<ModelTexture>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BakedQuad {
+    /// Counter-clockwise seen from the front: top left, bottom left, bottom right, top right
+    pub positions: [Vec3; 4],
+    /// Texture coordinates from 0 to 1 matching `positions`
+    pub uvs: [Vec2; 4],
+    pub normal: Vec3,
+    /// Index into `BakedModel::textures`
+    pub texture: usize,
+    /// The quad is skipped when the neighbour in this direction hides it
+    pub cull_face: Option<Direction>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelTexture {
+    pub name: String,
+    /// Image data embedded in the model file, usually PNG
+    pub data: Option<Vec<u8>>,
+    /// Where the texture lives when it isn't embedded
+    pub path: Option<String>,
+}
+
+pub struct BlockModel {
+    pub baked: BakedModel,
+}
+
+pub struct ItemModel {
+    pub baked: BakedModel,
+}
+
+/// Loads a model from a logical `namespace:path` in `vfs`
+#[profiling::function]
+pub fn load_block_model(vfs: &Vfs, path: &str) -> Result<BlockModel, ModelError> {
+    Ok(BlockModel { baked: load_baked(vfs, path)? })
+}
+
+/// Loads a model from a logical `namespace:path` in `vfs`
+#[profiling::function]
+pub fn load_item_model(vfs: &Vfs, path: &str) -> Result<ItemModel, ModelError> {
+    Ok(ItemModel { baked: load_baked(vfs, path)? })
+}
+
+fn load_baked(vfs: &Vfs, path: &str) -> Result<BakedModel, ModelError> {
+    if path.ends_with(".bbmodel") {
+        bbmodel::parse(&vfs.read_string(path)?)
+    } else {
+        Err(ResourceError::WrongType { path: PathBuf::from(path), expected: "bbmodel".to_string() }.into())
+    }
+}
+
+/// The corners of one face of the box between `from` and `to`, in `BakedQuad` order
+///
+/// Side faces are upright, the top face has north at its top edge and the bottom face has south at its top edge
+pub(crate) fn box_face(from: Vec3, to: Vec3, face: Direction) -> [Vec3; 4] {
+    let (x0, y0, z0) = from.into();
+    let (x1, y1, z1) = to.into();
+    let corners = match face {
+        Direction::NORTH => [(x1, y1, z0), (x1, y0, z0), (x0, y0, z0), (x0, y1, z0)],
+        Direction::SOUTH => [(x0, y1, z1), (x0, y0, z1), (x1, y0, z1), (x1, y1, z1)],
+        Direction::WEST => [(x1, y1, z1), (x1, y0, z1), (x1, y0, z0), (x1, y1, z0)],
+        Direction::EAST => [(x0, y1, z0), (x0, y0, z0), (x0, y0, z1), (x0, y1, z1)],
+        Direction::UP => [(x0, y1, z0), (x0, y1, z1), (x1, y1, z1), (x1, y1, z0)],
+        Direction::DOWN => [(x0, y0, z1), (x0, y0, z0), (x1, y0, z0), (x1, y0, z1)],
+    };
+    corners.map(Vec3::from)
+}
+
+/// The direction a face or cull face name in a model file stands for
+///
+/// Model files call the +X face `east`, which is `Direction::WEST` here, so east and west are swapped
+pub(crate) fn parse_face(name: &str) -> Result<Direction, ModelError> {
+    match name.parse() {
+        Ok(Direction::EAST) => Ok(Direction::WEST),
+        Ok(Direction::WEST) => Ok(Direction::EAST),
+        Ok(direction) => Ok(direction),
+        Err(_) => Err(ModelError::InvalidFace(name.to_string())),
+    }
+}
+
+/// The corners of the `[u1, v1, u2, v2]` rectangle in `BakedQuad` order, with the texture turned clockwise by
+/// `rotation` degrees
+pub(crate) fn face_uvs(uv: [f32; 4], rotation: f32) -> Result<[Vec2; 4], ModelError> {
+    if rotation % 90.0 != 0.0 {
+        return Err(ModelError::InvalidRotation(rotation));
+    }
+    let [u1, v1, u2, v2] = uv;
+    let corners = [Vec2::new(u1, v1), Vec2::new(u1, v2), Vec2::new(u2, v2), Vec2::new(u2, v1)];
+    let turns = (rotation / 90.0).rem_euclid(4.0) as usize;
+    Ok(std::array::from_fn(|i| corners[(i + turns) % 4]))
+}
+
+/// The outward normal of a quad in `BakedQuad` order, `None` if it has no area
+pub(crate) fn quad_normal(positions: &[Vec3; 4]) -> Option<Vec3> {
+    let normal = (positions[1] - positions[0]).cross(positions[2] - positions[1]);
+    let normal = if normal.length_squared() > f32::EPSILON {
+        normal
+    } else {
+        (positions[2] - positions[1]).cross(positions[3] - positions[2])
+    };
+    normal.try_normalize()
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    Resource(ResourceError),
+    Json(serde_json::Error),
+    InvalidTexture(String),
+    InvalidFace(String),
+    InvalidRotation(f32),
+    Key(KeyError),
+    /// Every model in the loop, starting and ending with the same one
+    Cycle(Vec<Key>),
+    MissingParent { model: Key, parent: Key },
+}
+
+impl From<ResourceError> for ModelError {
+    fn from(e: ResourceError) -> Self {
+        ModelError::Resource(e)
+    }
+}
+
+impl From<serde_json::Error> for ModelError {
+    fn from(e: serde_json::Error) -> Self {
+        ModelError::Json(e)
+    }
+}
+
+impl From<KeyError> for ModelError {
+    fn from(e: KeyError) -> Self {
+        ModelError::Key(e)
+    }
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Resource(e) => e.fmt(f),
+            ModelError::Json(e) => e.fmt(f),
+            ModelError::InvalidTexture(message) => write!(f, "Invalid texture: {message}"),
+            ModelError::InvalidFace(face) => write!(f, "Invalid face \"{face}\""),
+            ModelError::InvalidRotation(rotation) => write!(f, "Rotation {rotation} is not a multiple of 90 degrees"),
+            ModelError::Key(e) => e.fmt(f),
+            ModelError::Cycle(models) => {
+                let models: Vec<String> = models.iter().map(Key::to_string).collect();
+                write!(f, "Model parents form a cycle: {}", models.join(" -> "))
+            }
+            ModelError::MissingParent { model, parent } => write!(f, "Parent \"{parent}\" of model \"{model}\" does not exist"),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
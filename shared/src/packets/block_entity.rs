@@ -0,0 +1,77 @@
+use glam::IVec3;
+
+use crate::types::block_entity::BlockEntity;
+
+use super::PacketData;
+
+/// The synced fields of the block entity at a position, or that it was removed
+pub struct BlockEntityPacket {
+    pub position: IVec3,
+    /// A serialized `toml::Table`, `None` once the entity is removed
+    pub data: Option<String>,
+}
+
+impl BlockEntityPacket {
+    /// `None` tells clients the entity at `position` was removed
+    pub fn new(position: IVec3, entity: Option<&BlockEntity>) -> Result<Self, toml::ser::Error> {
+        Ok(Self {
+            position,
+            data: entity.map(|entity| toml::to_string(&entity.synced_data())).transpose()?,
+        })
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.data.is_none()
+    }
+
+    /// The synced fields, `None` if the entity was removed
+    pub fn fields(&self) -> Result<Option<toml::Table>, toml::de::Error> {
+        self.data.as_deref().map(toml::from_str).transpose()
+    }
+}
+
+impl PacketData for BlockEntityPacket {
+    #[profiling::function]
+    fn id() -> u32 {
+        2
+    }
+
+    #[profiling::function]
+    fn serialize(self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for coordinate in self.position.to_array() {
+            bytes.extend(coordinate.to_be_bytes());
+        }
+        match self.data {
+            Some(data) => {
+                bytes.push(0);
+                bytes.extend(data.into_bytes());
+            }
+            None => bytes.push(1),
+        }
+        bytes
+    }
+
+    #[profiling::function]
+    fn deserialize(bytes: &[u8]) -> Result<Self, &'static str> {
+        let coordinate = |index: usize| {
+            bytes
+                .get(index * 4..index * 4 + 4)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map_or(0, i32::from_be_bytes)
+        };
+
+        let data = match bytes.get(12) {
+            Some(0) => Some(
+                String::from_utf8(bytes[13..].to_vec()).map_err(|_| "Block entity data is not valid UTF-8")?,
+            ),
+            Some(_) => None,
+            None => return Err("Block entity packet is too short"),
+        };
+
+        Ok(Self {
+            position: IVec3::new(coordinate(0), coordinate(1), coordinate(2)),
+            data,
+        })
+    }
+}
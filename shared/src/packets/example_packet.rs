@@ -20,13 +20,13 @@ impl PacketData for ExamplePacket {
     }
 
     #[profiling::function]
-    fn deserialize(bytes: &[u8]) -> Self {
+    fn deserialize(bytes: &[u8]) -> Result<Self, &'static str> {
         let yl_bytes = &bytes[0..4];
         let t_bytes = &bytes[4..];
 
-        Self {
+        Ok(Self {
             years_left: u32::from_be_bytes(yl_bytes.try_into().unwrap_or_default()),
             text: String::from_utf8(t_bytes.try_into().unwrap_or_default()).unwrap_or_default(),
-        }
+        })
     }
 }
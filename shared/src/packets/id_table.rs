@@ -0,0 +1,25 @@
+use super::PacketData;
+
+/// A serialized `registry::IdTable`
+pub struct IdTablePacket {
+    pub table: String,
+}
+
+impl PacketData for IdTablePacket {
+    #[profiling::function]
+    fn id() -> u32 {
+        1
+    }
+
+    #[profiling::function]
+    fn serialize(self) -> Vec<u8> {
+        self.table.into_bytes()
+    }
+
+    #[profiling::function]
+    fn deserialize(bytes: &[u8]) -> Result<Self, &'static str> {
+        Ok(Self {
+            table: String::from_utf8(bytes.to_vec()).map_err(|_| "Id table is not valid UTF-8")?,
+        })
+    }
+}
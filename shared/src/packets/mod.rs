@@ -1,9 +1,13 @@
-use self::example_packet::ExamplePacket;
+use self::{block_entity::BlockEntityPacket, example_packet::ExamplePacket, id_table::IdTablePacket};
 
+pub mod block_entity;
 pub mod example_packet;
+pub mod id_table;
 
 pub enum Packet {
-    Example(ExamplePacket)
+    Example(ExamplePacket),
+    IdTable(IdTablePacket),
+    BlockEntity(BlockEntityPacket),
 }
 
 impl Packet {
@@ -11,6 +15,8 @@ impl Packet {
     pub fn serialize(self) -> Vec<u8> {
         let (id, data) = match self {
             Packet::Example(p) => (ExamplePacket::id(), p.serialize()),
+            Packet::IdTable(p) => (IdTablePacket::id(), p.serialize()),
+            Packet::BlockEntity(p) => (BlockEntityPacket::id(), p.serialize()),
         };
 
         let mut bytes = vec![];
@@ -20,11 +26,13 @@ impl Packet {
     }
 
     #[profiling::function]
-    pub fn deserialize(id: u32, data: &[u8]) -> Result<Self, &str> {
+    pub fn deserialize(id: u32, data: &[u8]) -> Result<Self, &'static str> {
         use Packet::*;
 
         match id {
-            0 => Ok(Example(ExamplePacket::deserialize(data))),
+            0 => Ok(Example(ExamplePacket::deserialize(data)?)),
+            1 => Ok(IdTable(IdTablePacket::deserialize(data)?)),
+            2 => Ok(BlockEntity(BlockEntityPacket::deserialize(data)?)),
             _ => Err("Packet not found"),
         }
     }
@@ -33,5 +41,7 @@ impl Packet {
 pub trait PacketData {
     fn id() -> u32;
     fn serialize(self) -> Vec<u8>;
-    fn deserialize(bytes: &[u8]) -> Self;
+    fn deserialize(bytes: &[u8]) -> Result<Self, &'static str>
+    where
+        Self: Sized;
 }
\ No newline at end of file
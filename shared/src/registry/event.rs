@@ -0,0 +1,32 @@
+use std::hash::Hash;
+
+use crate::types::Id;
+
+/// Sent to subscribers of a category whenever one of its entries changes.
+/// Owners are the namespaces of whoever registered the entry, which may differ from the entry's own namespace
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegistryEvent<T: Eq + Hash + PartialEq> {
+    Added {
+        id: Id<T>,
+        owner: String,
+    },
+    Replaced {
+        id: Id<T>,
+        old_owner: String,
+        new_owner: String,
+    },
+    Removed {
+        id: Id<T>,
+        owner: String,
+    },
+}
+
+impl<T: Eq + Hash + PartialEq> RegistryEvent<T> {
+    pub fn id(&self) -> &Id<T> {
+        match self {
+            RegistryEvent::Added { id, .. }
+            | RegistryEvent::Replaced { id, .. }
+            | RegistryEvent::Removed { id, .. } => id,
+        }
+    }
+}
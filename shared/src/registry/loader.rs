@@ -0,0 +1,252 @@
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+
+use crate::resources::{ResourceError, Vfs};
+use crate::types::{
+    block::{Block, BlockDefinition},
+    item::{Item, ItemDefinition},
+    loot::{LootEntryKind, LootTableDefinition},
+    recipe::{Ingredient, Recipe},
+    BuiltinData, BuiltinType, Data, Id, Key, KeyError, Type,
+};
+
+use super::Registry;
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> Registry<T, B, I, D> {
+    /// Registers every `<namespace>:blocks/*.toml`, `items/*.toml`, `recipes/*.toml` and `loot_tables/*.toml` definition
+    /// in `vfs` under `namespace`, followed by its tags. Broken files are skipped and reported, the rest are still registered
+    #[profiling::function]
+    pub fn load_addon<FB, FI>(
+        &mut self,
+        vfs: &Vfs,
+        namespace: &str,
+        block: FB,
+        item: FI,
+    ) -> Result<(), Vec<LoadError>>
+    where
+        FB: Fn(&Key, BlockDefinition) -> B,
+        FI: Fn(&Key, ItemDefinition) -> I,
+    {
+        let mut errors = vec![];
+
+        let blocks = load_definitions(vfs, namespace, "blocks", &mut errors, BlockDefinition::qualify);
+        for (file, key, definition) in blocks {
+            let data = Data::Builtin(BuiltinData::Block(block(&key, definition)));
+            let id = Id::new(Type::Builtin(BuiltinType::Block), key);
+            if let Err(e) = self.register_as(namespace, id, data) {
+                errors.push(LoadError::new(file, e));
+            }
+        }
+
+        let items = load_definitions(vfs, namespace, "items", &mut errors, ItemDefinition::qualify);
+        for (file, key, definition) in items {
+            let data = Data::Builtin(BuiltinData::Item(item(&key, definition)));
+            let id = Id::new(Type::Builtin(BuiltinType::Item), key);
+            if let Err(e) = self.register_as(namespace, id, data) {
+                errors.push(LoadError::new(file, e));
+            }
+        }
+
+        let recipes = load_definitions(vfs, namespace, "recipes", &mut errors, |_: &mut toml::Table, _| Ok(()));
+        for (file, key, table) in recipes {
+            let recipe = match Recipe::from_table(table, namespace) {
+                Ok(recipe) => recipe,
+                Err(e) => {
+                    errors.push(LoadError::new(file, e));
+                    continue;
+                }
+            };
+
+            let id = Id::new(Type::Builtin(BuiltinType::Recipe), key);
+            let items = recipe
+                .ingredients()
+                .into_iter()
+                .filter_map(|ingredient| match ingredient {
+                    Ingredient::Item(item) => Some(item.clone()),
+                    Ingredient::Tag(_) => None,
+                })
+                .chain(recipe.output().map(|output| output.item.clone()));
+            for item in items {
+                self.reference(id.clone(), Id::new(Type::Builtin(BuiltinType::Item), item));
+            }
+
+            if let Err(e) = self.register_as(namespace, id, Data::Builtin(BuiltinData::Recipe(recipe))) {
+                errors.push(LoadError::new(file, e));
+            }
+        }
+
+        let loot_tables = load_definitions(vfs, namespace, "loot_tables", &mut errors, |_: &mut LootTableDefinition, _| Ok(()));
+        for (file, key, definition) in loot_tables {
+            let table = match definition.into_loot_table(namespace) {
+                Ok(table) => table,
+                Err(e) => {
+                    errors.push(LoadError::new(file, e));
+                    continue;
+                }
+            };
+
+            let id = Id::new(Type::Builtin(BuiltinType::LootTable), key);
+            for entry in table.pools.iter().flat_map(|pool| &pool.entries) {
+                if let LootEntryKind::Item(item) = &entry.kind {
+                    self.reference(id.clone(), Id::new(Type::Builtin(BuiltinType::Item), item.clone()));
+                }
+            }
+
+            if let Err(e) = self.register_as(namespace, id, Data::Builtin(BuiltinData::LootTable(table))) {
+                errors.push(LoadError::new(file, e));
+            }
+        }
+
+        if let Err(tag_errors) = self.load_addon_tags(vfs, namespace) {
+            errors.extend(tag_errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Loads every namespace `vfs` provides files for with [`Self::load_addon`], reporting the problems of all of them
+    #[profiling::function]
+    pub fn load_addons<FB, FI>(&mut self, vfs: &Vfs, block: FB, item: FI) -> Result<(), Vec<LoadError>>
+    where
+        FB: Fn(&Key, BlockDefinition) -> B,
+        FI: Fn(&Key, ItemDefinition) -> I,
+    {
+        let mut errors = vec![];
+        for namespace in vfs.namespaces() {
+            if let Err(addon_errors) = self.load_addon(vfs, &namespace, &block, &item) {
+                errors.extend(addon_errors);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Parses every `.toml` file in the logical `<namespace>:<dir>` directory, keyed by `namespace` and the file stem
+#[profiling::function]
+pub(crate) fn load_definitions<Definition, Q>(
+    vfs: &Vfs,
+    namespace: &str,
+    dir: &str,
+    errors: &mut Vec<LoadError>,
+    qualify: Q,
+) -> Vec<(PathBuf, Key, Definition)>
+where
+    Definition: DeserializeOwned,
+    Q: Fn(&mut Definition, &str) -> Result<(), KeyError>,
+{
+    let files = match vfs.list(&format!("{namespace}:{dir}")) {
+        Ok(files) => files,
+        Err(e) => {
+            errors.push(e.into());
+            return vec![];
+        }
+    };
+
+    let mut definitions = vec![];
+    for file in files {
+        let Some(stem) = file.path.strip_suffix(".toml") else {
+            continue;
+        };
+        let path = file.to_string();
+        let location = vfs.source_of(&path).ok().flatten().unwrap_or_else(|| PathBuf::from(&path));
+
+        let name = stem.rsplit('/').next().unwrap_or(stem);
+        let key = match Key::new(namespace, name) {
+            Ok(key) => key,
+            Err(e) => {
+                errors.push(LoadError::new(location, e));
+                continue;
+            }
+        };
+
+        let contents = match vfs.read_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                errors.push(LoadError::new(location, e.message()));
+                continue;
+            }
+        };
+
+        let mut definition: Definition = match toml::from_str(&contents) {
+            Ok(definition) => definition,
+            Err(e) => {
+                errors.push(LoadError::from_toml(location, &contents, e));
+                continue;
+            }
+        };
+
+        if let Err(e) = qualify(&mut definition, namespace) {
+            errors.push(LoadError::new(location, e));
+            continue;
+        }
+
+        definitions.push((location, key, definition));
+    }
+
+    definitions
+}
+
+/// A problem with a single addon file
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadError {
+    pub file: PathBuf,
+    /// 1-based line and column, if the problem can be pinned down
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl LoadError {
+    pub fn new(file: impl Into<PathBuf>, message: impl fmt::Display) -> Self {
+        Self {
+            file: file.into(),
+            position: None,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn from_toml(file: impl Into<PathBuf>, contents: &str, error: toml::de::Error) -> Self {
+        Self {
+            file: file.into(),
+            position: error.span().map(|span| line_column(contents, span.start)),
+            message: error.message().to_string(),
+        }
+    }
+}
+
+/// 1-based line and column of a byte offset
+pub(crate) fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{line}:{column}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<ResourceError> for LoadError {
+    fn from(e: ResourceError) -> Self {
+        Self::new(e.path(), e.message())
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rand::{Rng, RngCore};
+
+use crate::types::{
+    block::Block,
+    item::Item,
+    loot::{LootCondition, LootContext, LootDrop, LootEntryKind, LootRange, LootTable},
+    state::StateId,
+    BuiltinData, BuiltinType, Data, Id, Key, Type,
+};
+
+use super::Registry;
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> Registry<T, B, I, D> {
+    pub fn loot_table(&self, key: &Key) -> Option<&LootTable> {
+        match self.entries.get(&Id::new(Type::Builtin(BuiltinType::LootTable), key.clone()))? {
+            Data::Builtin(BuiltinData::LootTable(table)) => Some(table),
+            _ => None,
+        }
+    }
+
+    /// Rolls a loot table, nothing drops if it does not exist. The same random state always gives the same drops
+    #[profiling::function]
+    pub fn roll_loot(&self, key: &Key, context: &LootContext, random: &mut dyn RngCore) -> Vec<LootDrop> {
+        let Some(table) = self.loot_table(key) else { return vec![] };
+        let mut drops = vec![];
+
+        for pool in &table.pools {
+            if !self.conditions_pass(&pool.conditions, context, random) {
+                continue;
+            }
+
+            for _ in 0..roll(pool.rolls, random) {
+                let entries: Vec<_> = pool
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.weight > 0 && self.conditions_pass(&entry.conditions, context, random))
+                    .collect();
+                let total: u64 = entries.iter().map(|entry| u64::from(entry.weight)).sum();
+                if total == 0 {
+                    continue;
+                }
+
+                let mut pick = random.gen_range(0..total);
+                let Some(entry) = entries.into_iter().find(|entry| {
+                    let found = pick < u64::from(entry.weight);
+                    pick = pick.saturating_sub(u64::from(entry.weight));
+                    found
+                }) else {
+                    continue;
+                };
+
+                let item = match &entry.kind {
+                    LootEntryKind::Item(item) => item.clone(),
+                    LootEntryKind::Tag(tag) => {
+                        let Some(members) = self.tag(&Type::Builtin(BuiltinType::Item), tag) else { continue };
+                        let mut members: Vec<&Key> = members.iter().collect();
+                        if members.is_empty() {
+                            continue;
+                        }
+                        members.sort();
+                        members[random.gen_range(0..members.len())].clone()
+                    }
+                    LootEntryKind::Empty => continue,
+                };
+
+                let count = roll(entry.count, random);
+                if count > 0 {
+                    drops.push(LootDrop { item, count });
+                }
+            }
+        }
+
+        drops
+    }
+
+    /// Rolls the loot table with the same key as the block a state belongs to
+    pub fn block_drops(&self, state: StateId, context: &LootContext, random: &mut dyn RngCore) -> Vec<LootDrop> {
+        let Some(block) = self.block_state(state) else { return vec![] };
+        let Some(id) = self.id_at(&Type::Builtin(BuiltinType::Block), block.block) else { return vec![] };
+        self.roll_loot(&id.key, context, random)
+    }
+
+    fn conditions_pass(&self, conditions: &[LootCondition], context: &LootContext, random: &mut dyn RngCore) -> bool {
+        conditions.iter().all(|condition| match condition {
+            LootCondition::Tool(tool) => context.tool.as_ref().is_some_and(|stack| self.ingredient_matches(tool, stack)),
+            LootCondition::Flag(flag) => context.flags.contains(flag),
+            LootCondition::NoFlag(flag) => !context.flags.contains(flag),
+            LootCondition::Chance(chance) => random.gen::<f32>() < *chance,
+        })
+    }
+}
+
+fn roll(range: LootRange, random: &mut dyn RngCore) -> u32 {
+    random.gen_range(range.min..=range.max.max(range.min))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::registry::{test_types::TestRegistry, TagEntry};
+    use crate::types::{
+        inventory::ItemStack,
+        loot::{LootError, LootTableDefinition, MAX_ROLLS},
+    };
+
+    use super::*;
+
+    fn key(key: &str) -> Key {
+        Key::parse(key).unwrap()
+    }
+
+    fn table(table: &str) -> Result<LootTable, LootError> {
+        toml::from_str::<LootTableDefinition>(table).unwrap().into_loot_table("test")
+    }
+
+    fn registry() -> TestRegistry {
+        let mut registry = TestRegistry::new();
+        registry.add_to_tag(
+            Type::Builtin(BuiltinType::Item),
+            key("test:pickaxes"),
+            vec![TagEntry::Id(key("test:iron_pickaxe"))],
+        );
+
+        let tables = [
+            ("ore", r#"[[pools]]
+rolls = { min = 1, max = 4 }
+entries = [
+    { item = "gem", count = { min = 1, max = 3 } },
+    { item = "stone", weight = 3 },
+    { weight = 2 },
+]"#),
+            ("weighted", r#"[[pools]]
+rolls = 200
+entries = [{ item = "common", weight = 3 }, { item = "rare" }, { item = "never", weight = 0 }]"#),
+            ("leaves", r##"[[pools]]
+conditions = [{ flag = "shears" }]
+entries = [{ item = "leaves" }]
+
+[[pools]]
+conditions = [{ no_flag = "shears" }]
+entries = [{ item = "sapling" }]
+
+[[pools]]
+conditions = [{ tool = "#pickaxes" }]
+entries = [{ item = "coal" }]"##),
+        ];
+        for (name, loot) in tables {
+            let id = Id::new(Type::Builtin(BuiltinType::LootTable), key(&format!("test:{name}")));
+            registry.register(id, Data::Builtin(BuiltinData::LootTable(table(loot).unwrap()))).unwrap();
+        }
+        registry
+    }
+
+    fn items(drops: &[LootDrop]) -> Vec<&str> {
+        drops.iter().map(|drop| drop.item.path.as_str()).collect()
+    }
+
+    #[test]
+    fn seeded() {
+        let registry = registry();
+        let roll = |seed| registry.roll_loot(&key("test:ore"), &LootContext::default(), &mut StdRng::seed_from_u64(seed));
+
+        for seed in 0..16 {
+            assert_eq!(roll(seed), roll(seed));
+        }
+        assert!((1..16).any(|seed| roll(seed) != roll(0)));
+        assert!(roll(0).iter().all(|drop| (1..=3).contains(&drop.count)));
+        assert!(registry.roll_loot(&key("test:missing"), &LootContext::default(), &mut StdRng::seed_from_u64(0)).is_empty());
+    }
+
+    #[test]
+    fn weights() {
+        let registry = registry();
+        let drops = registry.roll_loot(&key("test:weighted"), &LootContext::default(), &mut StdRng::seed_from_u64(0));
+        let items = items(&drops);
+        let common = items.iter().filter(|item| **item == "common").count();
+        let rare = items.iter().filter(|item| **item == "rare").count();
+
+        assert_eq!(common + rare, 200);
+        assert!(common > rare * 2, "{common} common, {rare} rare");
+        assert!(!items.contains(&"never"));
+    }
+
+    #[test]
+    fn conditions() {
+        let registry = registry();
+        let roll = |context: &LootContext| registry.roll_loot(&key("test:leaves"), context, &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(items(&roll(&LootContext::default())), ["sapling"]);
+        assert_eq!(items(&roll(&LootContext::default().with_flag("shears"))), ["leaves"]);
+
+        let pickaxe = ItemStack::new(key("test:iron_pickaxe"), 1, 1);
+        assert_eq!(items(&roll(&LootContext::default().with_tool(pickaxe))), ["sapling", "coal"]);
+        let shovel = ItemStack::new(key("test:iron_shovel"), 1, 1);
+        assert_eq!(items(&roll(&LootContext::default().with_tool(shovel))), ["sapling"]);
+    }
+
+    #[test]
+    fn limits() {
+        let rolls = format!("[[pools]]\nrolls = {{ min = 1, max = {} }}\nentries = [{{ item = \"gem\" }}]", MAX_ROLLS + 1);
+        assert_eq!(table(&rolls), Err(LootError::Rolls(MAX_ROLLS + 1)));
+
+        let weight = "[[pools]]\nentries = [{ item = \"gem\", weight = 4000000000 }, { weight = 4000000000 }]";
+        assert_eq!(table(weight), Err(LootError::Weight(8_000_000_000)));
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use mlua::{Lua, UserData, UserDataMethods};
+
+use crate::types::{block::Block, item::Item, BuiltinType, Key, Type};
+
+use super::Registry;
+
+/// A snapshot of one category's tags that scripts can query with `tags:contains(tag, key)` and `tags:members(tag)`
+#[derive(Clone, Default)]
+pub struct LuaTags(Arc<HashMap<Key, HashSet<Key>>>);
+
+impl UserData for LuaTags {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("contains", |_, tags, (tag, key): (String, String)| {
+            let tag = Key::parse(&tag).map_err(mlua::Error::external)?;
+            let key = Key::parse(&key).map_err(mlua::Error::external)?;
+            Ok(tags.0.get(&tag).is_some_and(|members| members.contains(&key)))
+        });
+
+        methods.add_method("members", |_, tags, tag: String| {
+            let tag = Key::parse(&tag).map_err(mlua::Error::external)?;
+            let mut members: Vec<String> = tags
+                .0
+                .get(&tag)
+                .into_iter()
+                .flatten()
+                .map(Key::to_string)
+                .collect();
+            members.sort();
+            Ok(members)
+        });
+    }
+}
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> Registry<T, B, I, D> {
+    pub fn lua_tags(&self, category: &Type<T>) -> LuaTags {
+        LuaTags(Arc::new(self.tags(category).cloned().unwrap_or_default()))
+    }
+
+    /// Exposes the current block and item tags to scripts as the `tags.blocks` and `tags.items` globals
+    #[profiling::function]
+    pub fn register_lua(&self, lua: &Lua) -> mlua::Result<()> {
+        let tags = lua.create_table()?;
+        tags.set("blocks", self.lua_tags(&Type::Builtin(BuiltinType::Block)))?;
+        tags.set("items", self.lua_tags(&Type::Builtin(BuiltinType::Item)))?;
+        lua.globals().set("tags", tags)
+    }
+}
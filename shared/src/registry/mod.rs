@@ -0,0 +1,441 @@
+use std::hash::Hash;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::mpsc::Receiver;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::broadcast::Broadcast;
+use crate::packets::id_table::IdTablePacket;
+use crate::types::{block::Block, item::Item, state::{StateDefinition, StateError}, BuiltinData, BuiltinType, Data, Id, Key, Type};
+use crate::util::edit_distance;
+use std::fmt::Debug;
+
+use self::recipe::RecipeBucket;
+
+pub use self::{event::RegistryEvent, loader::LoadError, lua::LuaTags, tag::{TagDefinition, TagEntry}};
+
+mod event;
+mod loader;
+mod loot;
+mod lua;
+mod recipe;
+mod state;
+mod tag;
+#[cfg(test)]
+mod test_types;
+
+pub struct Registry<
+    CustomType: Clone + Debug + Eq + Hash + PartialEq,
+    B: Block,
+    I: Item,
+    CustomData,
+> {
+    entries: HashMap<Id<CustomType>, Data<B, I, CustomData>>,
+    owners: HashMap<Id<CustomType>, String>,
+    subscribers: HashMap<Type<CustomType>, Broadcast<RegistryEvent<CustomType>>>,
+    indices: HashMap<Id<CustomType>, u32>,
+    categories: HashMap<Type<CustomType>, Vec<Id<CustomType>>>,
+    references: Vec<(Id<CustomType>, Id<CustomType>)>,
+    tags: HashMap<Type<CustomType>, HashMap<Key, Vec<TagEntry>>>,
+    resolved_tags: HashMap<Type<CustomType>, HashMap<Key, HashSet<Key>>>,
+    recipes: HashMap<RecipeBucket, Vec<Id<CustomType>>>,
+    state_definitions: Vec<StateDefinition>,
+    /// Local state count of every registered block, checked on registration so state ids can't overflow
+    block_states: HashMap<Id<CustomType>, u32>,
+    state_offsets: Vec<u32>,
+    state_count: u32,
+    frozen: bool,
+}
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> Registry<T, B, I, D> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            owners: HashMap::new(),
+            subscribers: HashMap::new(),
+            indices: HashMap::new(),
+            categories: HashMap::new(),
+            references: vec![],
+            tags: HashMap::new(),
+            resolved_tags: HashMap::new(),
+            recipes: HashMap::new(),
+            state_definitions: vec![],
+            block_states: HashMap::new(),
+            state_offsets: vec![],
+            state_count: 0,
+            frozen: false,
+        }
+    }
+
+    /// Registers an entry owned by its own namespace. Once frozen, only existing entries can be replaced
+    #[profiling::function]
+    pub fn register(&mut self, id: Id<T>, entry: Data<B, I, D>) -> Result<(), RegistryError<T>> {
+        let owner = id.namespace().to_string();
+        self.register_as(owner, id, entry)
+    }
+
+    /// Registers an entry on behalf of `owner`, e.g. an addon overriding another addon's entry
+    #[profiling::function]
+    pub fn register_as(&mut self, owner: impl Into<String>, id: Id<T>, entry: Data<B, I, D>) -> Result<(), RegistryError<T>> {
+        if self.frozen && !self.entries.contains_key(&id) {
+            return Err(RegistryError::Frozen(id));
+        }
+
+        let states = self.check_states(&id, &entry)?;
+        match states {
+            Some(states) => self.block_states.insert(id.clone(), states),
+            None => self.block_states.remove(&id),
+        };
+
+        let owner = owner.into();
+        let event = match self.owners.insert(id.clone(), owner.clone()) {
+            Some(old_owner) => RegistryEvent::Replaced {
+                id: id.clone(),
+                old_owner,
+                new_owner: owner,
+            },
+            None => RegistryEvent::Added {
+                id: id.clone(),
+                owner,
+            },
+        };
+
+        self.unindex_recipe(&id);
+        self.entries.insert(id.clone(), entry);
+        self.index_recipe(&id);
+        self.notify(event);
+        Ok(())
+    }
+
+    /// Entries cannot be removed once frozen, as that would leave a hole in the index table
+    #[profiling::function]
+    pub fn unregister(&mut self, id: &Id<T>) -> Result<Data<B, I, D>, RegistryError<T>> {
+        if self.frozen {
+            return Err(RegistryError::Frozen(id.clone()));
+        }
+
+        let entry = self.try_remove(id)?;
+        self.block_states.remove(id);
+        self.unindex_recipe(id);
+        let owner = self.owners.remove(id).unwrap_or_default();
+        self.notify(RegistryEvent::Removed {
+            id: id.clone(),
+            owner,
+        });
+        Ok(entry)
+    }
+
+    fn try_remove(&mut self, id: &Id<T>) -> Result<Data<B, I, D>, RegistryError<T>> {
+        match self.entries.remove(id) {
+            Some(entry) => Ok(entry),
+            None => Err(RegistryError::NotFound {
+                id: id.clone(),
+                suggestions: self.suggestions(id),
+            }),
+        }
+    }
+
+    /// Receives every change made to `category` from now on
+    pub fn subscribe(&mut self, category: Type<T>) -> Receiver<RegistryEvent<T>> {
+        self.subscribers.entry(category).or_default().register()
+    }
+
+    fn notify(&mut self, event: RegistryEvent<T>) {
+        if let Some(subscribers) = self.subscribers.get_mut(&event.id().r#type) {
+            subscribers.broadcast(event);
+        }
+    }
+
+    /// The namespace that most recently registered this entry
+    pub fn owner(&self, id: &Id<T>) -> Option<&str> {
+        self.owners.get(id).map(String::as_str)
+    }
+
+    #[profiling::function]
+    pub fn register_entries(&mut self, entries: Vec<(Id<T>, Data<B, I, D>)>) -> Result<(), RegistryError<T>> {
+        for (id, entry) in entries {
+            self.register(id, entry)?;
+        }
+        Ok(())
+    }
+
+    /// Panics if the entry does not exist, prefer `try_get` for ids that come from addons
+    pub fn get(&self, id: &Id<T>) -> &Data<B, I, D> {
+        match self.try_get(id) {
+            Ok(entry) => entry,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    #[profiling::function]
+    pub fn try_get(&self, id: &Id<T>) -> Result<&Data<B, I, D>, RegistryError<T>> {
+        self.entries.get(id).ok_or_else(|| RegistryError::NotFound {
+            id: id.clone(),
+            suggestions: self.suggestions(id),
+        })
+    }
+
+    /// The item registered under a key
+    pub fn item(&self, key: &Key) -> Option<&I> {
+        match self.entries.get(&Id::new(Type::Builtin(BuiltinType::Item), key.clone()))? {
+            Data::Builtin(BuiltinData::Item(item)) => Some(item),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, id: &Id<T>) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    /// Ids in the same category and namespace with a similar path, closest first
+    #[profiling::function]
+    pub fn suggestions(&self, id: &Id<T>) -> Vec<Id<T>> {
+        let max_distance = (id.path().len() / 3).max(2);
+
+        let mut suggestions: Vec<(usize, &Id<T>)> = self
+            .entries
+            .keys()
+            .filter(|other| other.r#type == id.r#type && other.namespace() == id.namespace())
+            .map(|other| (edit_distance(id.path(), other.path()), other))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        suggestions.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.key.cmp(&b.1.key)));
+
+        suggestions.into_iter().take(3).map(|(_, other)| other.clone()).collect()
+    }
+
+    /// Records that `from` depends on `to`, checked later by `validate`
+    pub fn reference(&mut self, from: Id<T>, to: Id<T>) {
+        self.references.push((from, to));
+    }
+
+    /// Reports every recorded reference and tag entry that points at a missing entry, as well as broken nested tags
+    #[profiling::function]
+    pub fn validate(&self) -> Result<(), Vec<RegistryError<T>>> {
+        let mut errors: Vec<RegistryError<T>> = self
+            .references
+            .iter()
+            .filter(|(_, to)| !self.entries.contains_key(to))
+            .map(|(from, to)| RegistryError::Dangling {
+                from: from.clone(),
+                to: to.clone(),
+                suggestions: self.suggestions(to),
+            })
+            .collect();
+        self.validate_tags(&mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Assigns every entry a dense index within its category, ordered by key
+    #[profiling::function]
+    pub fn freeze(&mut self) {
+        if self.frozen {
+            return;
+        }
+
+        let mut categories: HashMap<Type<T>, Vec<Id<T>>> = HashMap::new();
+        for id in self.entries.keys() {
+            categories.entry(id.r#type.clone()).or_default().push(id.clone());
+        }
+        for ids in categories.values_mut() {
+            ids.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+
+        self.set_categories(categories);
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn index_of(&self, id: &Id<T>) -> Option<u32> {
+        self.indices.get(id).copied()
+    }
+
+    pub fn id_at(&self, category: &Type<T>, index: u32) -> Option<&Id<T>> {
+        self.categories.get(category)?.get(index as usize)
+    }
+
+    pub fn get_by_index(&self, category: &Type<T>, index: u32) -> Option<&Data<B, I, D>> {
+        self.entries.get(self.id_at(category, index)?)
+    }
+
+    /// Number of indices in a category, only known once frozen
+    pub fn len(&self, category: &Type<T>) -> usize {
+        self.categories.get(category).map_or(0, Vec::len)
+    }
+
+    #[profiling::function]
+    pub fn id_table(&self) -> Result<IdTable<T>, RegistryError<T>> {
+        if !self.frozen {
+            return Err(RegistryError::NotFrozen);
+        }
+
+        Ok(IdTable {
+            categories: self
+                .categories
+                .iter()
+                .map(|(category, ids)| IdTableCategory {
+                    r#type: category.clone(),
+                    keys: ids.iter().map(|id| id.key.clone()).collect(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Reorders local indices to match a table received from a server.
+    /// Local entries the table does not mention are appended after the table's entries
+    #[profiling::function]
+    pub fn remap(&mut self, table: &IdTable<T>) -> Result<(), RegistryError<T>> {
+        let mut missing = vec![];
+        let mut categories: HashMap<Type<T>, Vec<Id<T>>> = HashMap::new();
+
+        for category in &table.categories {
+            let ids = categories.entry(category.r#type.clone()).or_default();
+            for key in &category.keys {
+                let id = Id::new(category.r#type.clone(), key.clone());
+                if !self.entries.contains_key(&id) {
+                    missing.push(id.clone());
+                }
+                ids.push(id);
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(RegistryError::Missing(missing));
+        }
+
+        let known: HashSet<&Id<T>> = categories.values().flatten().collect();
+        let mut extra: Vec<Id<T>> = self
+            .entries
+            .keys()
+            .filter(|id| !known.contains(id))
+            .cloned()
+            .collect();
+        extra.sort_by(|a, b| a.key.cmp(&b.key));
+        for id in extra {
+            categories.entry(id.r#type.clone()).or_default().push(id);
+        }
+
+        self.set_categories(categories);
+        Ok(())
+    }
+
+    fn set_categories(&mut self, categories: HashMap<Type<T>, Vec<Id<T>>>) {
+        self.indices = categories
+            .values()
+            .flat_map(|ids| ids.iter().enumerate().map(|(index, id)| (id.clone(), index as u32)))
+            .collect();
+        self.categories = categories;
+        self.frozen = true;
+        self.assign_states();
+    }
+}
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> Default for Registry<T, B, I, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps the dense indices of a frozen registry back to keys, sent from server to client
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IdTable<T: Eq + Hash + PartialEq> {
+    pub categories: Vec<IdTableCategory<T>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IdTableCategory<T: Eq + Hash + PartialEq> {
+    pub r#type: Type<T>,
+    pub keys: Vec<Key>,
+}
+
+impl<T: Eq + Hash + PartialEq + Serialize + DeserializeOwned> IdTable<T> {
+    #[profiling::function]
+    pub fn to_packet(&self) -> Result<IdTablePacket, toml::ser::Error> {
+        Ok(IdTablePacket {
+            table: toml::to_string(self)?,
+        })
+    }
+
+    #[profiling::function]
+    pub fn from_packet(packet: &IdTablePacket) -> Result<Self, toml::de::Error> {
+        toml::from_str(&packet.table)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RegistryError<T: Eq + Hash + PartialEq> {
+    NotFound {
+        id: Id<T>,
+        suggestions: Vec<Id<T>>,
+    },
+    Dangling {
+        from: Id<T>,
+        to: Id<T>,
+        suggestions: Vec<Id<T>>,
+    },
+    MissingTag {
+        tag: Id<T>,
+        missing: Id<T>,
+    },
+    TagCycle(Id<T>),
+    Frozen(Id<T>),
+    NotFrozen,
+    Missing(Vec<Id<T>>),
+    States {
+        id: Id<T>,
+        error: StateError,
+    },
+}
+
+impl<T: Debug + Eq + Hash + PartialEq> fmt::Display for RegistryError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::NotFound { id, suggestions } => {
+                write!(f, "Entry {:?} {} not found", id.r#type, id)?;
+                write_suggestions(f, suggestions)
+            }
+            RegistryError::Dangling { from, to, suggestions } => {
+                write!(f, "{:?} {} references missing {:?} {}", from.r#type, from, to.r#type, to)?;
+                write_suggestions(f, suggestions)
+            }
+            RegistryError::MissingTag { tag, missing } => {
+                write!(f, "{:?} tag #{} includes missing tag #{}", tag.r#type, tag, missing)
+            }
+            RegistryError::TagCycle(tag) => write!(f, "{:?} tag #{} includes itself", tag.r#type, tag),
+            RegistryError::Frozen(id) => write!(f, "Cannot add {:?} {} to a frozen registry", id.r#type, id),
+            RegistryError::NotFrozen => write!(f, "Registry has not been frozen yet"),
+            RegistryError::Missing(ids) => {
+                write!(f, "Registry is missing {} entries: ", ids.len())?;
+                for (i, id) in ids.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?} {}", id.r#type, id)?;
+                }
+                Ok(())
+            }
+            RegistryError::States { id, error } => write!(f, "{:?} {}: {}", id.r#type, id, error),
+        }
+    }
+}
+
+impl<T: Debug + Eq + Hash + PartialEq> std::error::Error for RegistryError<T> {}
+
+fn write_suggestions<T: Eq + Hash + PartialEq>(f: &mut fmt::Formatter<'_>, suggestions: &[Id<T>]) -> fmt::Result {
+    for (i, suggestion) in suggestions.iter().enumerate() {
+        let separator = if i == 0 { ", did you mean " } else { " or " };
+        write!(f, "{separator}{suggestion}")?;
+    }
+    if !suggestions.is_empty() {
+        write!(f, "?")?;
+    }
+    Ok(())
+}
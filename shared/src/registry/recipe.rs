@@ -0,0 +1,285 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::types::{
+    block::Block,
+    inventory::ItemStack,
+    item::Item,
+    recipe::{Ingredient, Recipe},
+    BuiltinData, BuiltinType, Data, Id, Key, Type,
+};
+
+use super::Registry;
+
+/// Recipes that can only match the same inputs share a bucket, so lookups only compare against a few candidates
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(super) enum RecipeBucket {
+    /// Shaped and shapeless recipes by how many ingredients they take
+    Crafting(usize),
+    Processing(Key),
+    Custom(Key),
+}
+
+impl RecipeBucket {
+    fn of(recipe: &Recipe) -> Self {
+        match recipe {
+            Recipe::Shaped { ingredients, .. } => RecipeBucket::Crafting(ingredients.iter().flatten().count()),
+            Recipe::Shapeless { ingredients, .. } => RecipeBucket::Crafting(ingredients.len()),
+            Recipe::Processing { kind, .. } => RecipeBucket::Processing(kind.clone()),
+            Recipe::Custom { kind, .. } => RecipeBucket::Custom(kind.clone()),
+        }
+    }
+}
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> Registry<T, B, I, D> {
+    pub fn recipe(&self, id: &Id<T>) -> Option<&Recipe> {
+        match self.entries.get(id)? {
+            Data::Builtin(BuiltinData::Recipe(recipe)) => Some(recipe),
+            _ => None,
+        }
+    }
+
+    pub(super) fn index_recipe(&mut self, id: &Id<T>) {
+        let Some(bucket) = self.recipe(id).map(RecipeBucket::of) else { return };
+        let ids = self.recipes.entry(bucket).or_default();
+        if let Err(index) = ids.binary_search_by(|other| other.key.cmp(&id.key)) {
+            ids.insert(index, id.clone());
+        }
+    }
+
+    pub(super) fn unindex_recipe(&mut self, id: &Id<T>) {
+        if id.r#type == Type::Builtin(BuiltinType::Recipe) {
+            for ids in self.recipes.values_mut() {
+                ids.retain(|other| other != id);
+            }
+        }
+    }
+
+    fn bucket(&self, bucket: &RecipeBucket) -> impl Iterator<Item = (&Id<T>, &Recipe)> {
+        self.recipes
+            .get(bucket)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| Some((id, self.recipe(id)?)))
+    }
+
+    pub fn ingredient_matches(&self, ingredient: &Ingredient, stack: &ItemStack) -> bool {
+        match ingredient {
+            Ingredient::Item(item) => stack.item == *item,
+            Ingredient::Tag(tag) => self
+                .tag(&Type::Builtin(BuiltinType::Item), tag)
+                .is_some_and(|members| members.contains(&stack.item)),
+        }
+    }
+
+    /// The shaped or shapeless recipe a crafting grid of `width` columns makes, if any.
+    /// Empty rows and columns around the items are ignored
+    #[profiling::function]
+    pub fn find_crafting(&self, grid: &[Option<ItemStack>], width: usize) -> Option<(&Id<T>, &Recipe)> {
+        let grid = trim(grid, width)?;
+        let stacks: Vec<&ItemStack> = grid.cells.iter().flatten().copied().collect();
+
+        self.bucket(&RecipeBucket::Crafting(stacks.len())).find(|(_, recipe)| match recipe {
+            Recipe::Shaped { width, height, ingredients, .. } => {
+                *width == grid.width
+                    && *height == grid.height
+                    && (self.shaped_matches(&grid, ingredients, false) || self.shaped_matches(&grid, ingredients, true))
+            }
+            Recipe::Shapeless { ingredients, .. } => self.shapeless_matches(ingredients, &stacks, &mut vec![false; stacks.len()]),
+            _ => false,
+        })
+    }
+
+    /// The processing recipe of `kind` that accepts `input`, if any
+    #[profiling::function]
+    pub fn find_processing(&self, kind: &Key, input: &ItemStack) -> Option<(&Id<T>, &Recipe)> {
+        self.bucket(&RecipeBucket::Processing(kind.clone())).find(|(_, recipe)| match recipe {
+            Recipe::Processing { input: ingredient, .. } => self.ingredient_matches(ingredient, input),
+            _ => false,
+        })
+    }
+
+    /// Every recipe of an addon defined type, ordered by key
+    pub fn custom_recipes(&self, kind: &Key) -> impl Iterator<Item = (&Id<T>, &Recipe)> {
+        self.bucket(&RecipeBucket::Custom(kind.clone()))
+    }
+
+    fn shaped_matches(&self, grid: &Grid, ingredients: &[Option<Ingredient>], mirrored: bool) -> bool {
+        grid.cells.iter().enumerate().all(|(index, stack)| {
+            let (x, y) = (index % grid.width, index / grid.width);
+            let x = if mirrored { grid.width - 1 - x } else { x };
+            match (&ingredients[x + y * grid.width], stack) {
+                (None, None) => true,
+                (Some(ingredient), Some(stack)) => self.ingredient_matches(ingredient, stack),
+                _ => false,
+            }
+        })
+    }
+
+    /// Pairs every ingredient with its own stack, backtracking since tags can overlap
+    fn shapeless_matches(&self, ingredients: &[Ingredient], stacks: &[&ItemStack], used: &mut Vec<bool>) -> bool {
+        let Some((ingredient, rest)) = ingredients.split_first() else { return true };
+
+        for (index, stack) in stacks.iter().enumerate() {
+            if used[index] || !self.ingredient_matches(ingredient, stack) {
+                continue;
+            }
+            used[index] = true;
+            if self.shapeless_matches(rest, stacks, used) {
+                return true;
+            }
+            used[index] = false;
+        }
+        false
+    }
+}
+
+/// The smallest rectangle of a crafting grid holding every stack
+struct Grid<'a> {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<&'a ItemStack>>,
+}
+
+/// `None` if the grid is empty or isn't made of whole rows of `width`
+fn trim(grid: &[Option<ItemStack>], width: usize) -> Option<Grid<'_>> {
+    if width == 0 || !grid.len().is_multiple_of(width) {
+        return None;
+    }
+
+    let filled = |index: usize| grid[index].as_ref().is_some_and(|stack| !stack.is_empty());
+    let positions: Vec<(usize, usize)> = (0..grid.len())
+        .filter(|index| filled(*index))
+        .map(|index| (index % width, index / width))
+        .collect();
+
+    let left = positions.iter().map(|(x, _)| *x).min()?;
+    let right = positions.iter().map(|(x, _)| *x).max()?;
+    let top = positions.iter().map(|(_, y)| *y).min()?;
+    let bottom = positions.iter().map(|(_, y)| *y).max()?;
+
+    let mut cells = vec![];
+    for y in top..=bottom {
+        for x in left..=right {
+            let index = x + y * width;
+            cells.push(if filled(index) { grid[index].as_ref() } else { None });
+        }
+    }
+
+    Some(Grid {
+        width: right - left + 1,
+        height: bottom - top + 1,
+        cells,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::{test_types::TestRegistry, TagEntry};
+
+    use super::*;
+
+    fn key(key: &str) -> Key {
+        Key::parse(key).unwrap()
+    }
+
+    fn stack(item: &str) -> Option<ItemStack> {
+        Some(ItemStack::new(key(item), 1, 64))
+    }
+
+    fn registry() -> TestRegistry {
+        let mut registry = TestRegistry::new();
+        registry.add_to_tag(
+            Type::Builtin(BuiltinType::Item),
+            key("test:planks"),
+            vec![TagEntry::Id(key("test:oak_planks")), TagEntry::Id(key("test:birch_planks"))],
+        );
+
+        let recipes = [
+            ("stick", r##"type = "shaped"
+pattern = ["#", "#"]
+key = { "#" = "#planks" }
+result = { item = "stick", count = 4 }"##),
+            ("table", r###"type = "shaped"
+pattern = ["##", "# "]
+key = { "#" = "#planks" }
+result = { item = "table" }"###),
+            ("dye", r##"type = "shapeless"
+ingredients = ["#planks", "stick"]
+result = { item = "dye" }"##),
+            ("ingot", r#"type = "processing"
+kind = "smelting"
+input = "ore"
+result = { item = "ingot" }"#),
+        ];
+        for (name, recipe) in recipes {
+            let recipe = Recipe::from_table(toml::from_str(recipe).unwrap(), "test").unwrap();
+            let id = Id::new(Type::Builtin(BuiltinType::Recipe), key(&format!("test:{name}")));
+            registry.register(id, Data::Builtin(BuiltinData::Recipe(recipe))).unwrap();
+        }
+        registry
+    }
+
+    fn crafted(registry: &TestRegistry, grid: &[Option<ItemStack>], width: usize) -> Option<String> {
+        registry.find_crafting(grid, width).map(|(id, _)| id.key.to_string())
+    }
+
+    #[test]
+    fn shaped() {
+        let registry = registry();
+
+        // Anywhere in the grid, with any planks
+        let mut grid = vec![None; 9];
+        grid[2] = stack("test:oak_planks");
+        grid[5] = stack("test:birch_planks");
+        assert_eq!(crafted(&registry, &grid, 3).as_deref(), Some("test:stick"));
+
+        // Trimmed to the bottom right corner and mirrored
+        let mut grid = vec![None; 9];
+        grid[4] = stack("test:oak_planks");
+        grid[5] = stack("test:oak_planks");
+        grid[8] = stack("test:oak_planks");
+        assert_eq!(crafted(&registry, &grid, 3).as_deref(), Some("test:table"));
+
+        // Not a tagged item
+        grid[8] = stack("test:stone");
+        assert_eq!(crafted(&registry, &grid, 3), None);
+    }
+
+    #[test]
+    fn shapeless() {
+        let registry = registry();
+
+        let mut grid = vec![None; 4];
+        grid[0] = stack("test:stick");
+        grid[3] = stack("test:birch_planks");
+        assert_eq!(crafted(&registry, &grid, 2).as_deref(), Some("test:dye"));
+
+        grid[0] = stack("test:oak_planks");
+        assert_eq!(crafted(&registry, &grid, 2), None);
+    }
+
+    #[test]
+    fn processing() {
+        let registry = registry();
+        let ore = ItemStack::new(key("test:ore"), 3, 64);
+        let (id, _) = registry.find_processing(&key("test:smelting"), &ore).unwrap();
+        assert_eq!(id.key, key("test:ingot"));
+        assert!(registry.find_processing(&key("test:blasting"), &ore).is_none());
+    }
+
+    #[test]
+    fn trimmed() {
+        let mut grid = vec![None; 9];
+        grid[4] = stack("test:a");
+        grid[8] = stack("test:b");
+        let trimmed = trim(&grid, 3).unwrap();
+        assert_eq!((trimmed.width, trimmed.height), (2, 2));
+        let items: Vec<_> = trimmed.cells.iter().map(|cell| cell.map(|stack| stack.item.path.as_str())).collect();
+        assert_eq!(items, [Some("a"), None, None, Some("b")]);
+
+        assert!(trim(&[None, None, None, None], 2).is_none());
+        assert!(trim(&grid[..5], 3).is_none());
+        assert!(trim(&grid, 0).is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::shape::Shape;
+use crate::types::{
+    block::Block,
+    block_entity::BlockEntity,
+    item::Item,
+    context::States,
+    state::{BlockState, StateDefinition, StateError, StateId, StateKey, Value},
+    BuiltinData, BuiltinType, Data, Id, Key, Type,
+};
+
+use super::{Registry, RegistryError};
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> Registry<T, B, I, D> {
+    /// Lays out the states of every block in index order, right after `StateId::AIR`
+    #[profiling::function]
+    pub(super) fn assign_states(&mut self) {
+        let category = Type::Builtin(BuiltinType::Block);
+
+        let definitions: Vec<StateDefinition> = self
+            .categories
+            .get(&category)
+            .into_iter()
+            .flatten()
+            .map(|id| match self.entries.get(id) {
+                // Validated when the block was registered
+                Some(Data::Builtin(BuiltinData::Block(block))) => {
+                    StateDefinition::new(block.properties()).unwrap_or_default()
+                }
+                _ => StateDefinition::default(),
+            })
+            .collect();
+
+        let mut offset = StateId::AIR.0 + 1;
+        self.state_offsets = definitions
+            .iter()
+            .map(|definition| {
+                let start = offset;
+                offset += definition.count();
+                start
+            })
+            .collect();
+        self.state_definitions = definitions;
+        self.state_count = offset;
+    }
+
+    /// The local state count of `entry` if it is a block, failing if its states or the states of all blocks
+    /// together would not fit into a `StateId`
+    pub(super) fn check_states(&self, id: &Id<T>, entry: &Data<B, I, D>) -> Result<Option<u32>, RegistryError<T>> {
+        let Data::Builtin(BuiltinData::Block(block)) = entry else {
+            return Ok(None);
+        };
+
+        let error = |error| RegistryError::States { id: id.clone(), error };
+        let count = StateDefinition::new(block.properties()).map_err(error)?.count();
+        let others: u64 = self
+            .block_states
+            .iter()
+            .filter(|(other, _)| *other != id)
+            .map(|(_, count)| u64::from(*count))
+            .sum();
+        if u64::from(StateId::AIR.0) + 1 + others + u64::from(count) > u64::from(u32::MAX) {
+            return Err(error(StateError::TooManyStates));
+        }
+        Ok(Some(count))
+    }
+
+    /// Total number of state ids including `StateId::AIR`, only known once frozen
+    pub fn state_count(&self) -> u32 {
+        self.state_count
+    }
+
+    pub fn state_definition(&self, block: u32) -> Option<&StateDefinition> {
+        self.state_definitions.get(block as usize)
+    }
+
+    pub fn state_id(&self, state: BlockState) -> Option<StateId> {
+        let definition = self.state_definition(state.block)?;
+        if state.state >= definition.count() {
+            return None;
+        }
+        Some(StateId(self.state_offsets[state.block as usize] + state.state))
+    }
+
+    /// The first state of a block, where every property has its first value
+    pub fn default_state(&self, id: &Id<T>) -> Option<StateId> {
+        self.state_id(BlockState {
+            block: self.index_of(id)?,
+            state: 0,
+        })
+    }
+
+    /// `None` for `StateId::AIR` and ids past the last state
+    pub fn block_state(&self, id: StateId) -> Option<BlockState> {
+        if id == StateId::AIR || id.0 >= self.state_count {
+            return None;
+        }
+
+        let block = self.state_offsets.partition_point(|offset| *offset <= id.0) - 1;
+        Some(BlockState {
+            block: block as u32,
+            state: id.0 - self.state_offsets[block],
+        })
+    }
+
+    /// The block key and property values of a state, `None` for `StateId::AIR` and unknown ids
+    pub fn state_key(&self, id: StateId) -> Option<StateKey> {
+        let state = self.block_state(id)?;
+        let block = self.id_at(&Type::Builtin(BuiltinType::Block), state.block)?.key.clone();
+        let properties = self
+            .state_definition(state.block)?
+            .values(state.state)
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Some(StateKey { block, properties })
+    }
+
+    /// The state a `StateKey` describes, `None` if its block isn't registered.
+    /// Properties the block no longer has are ignored, missing or invalid ones keep their first value
+    pub fn state_from_key(&self, key: &StateKey) -> Option<StateId> {
+        let block = self.index_of(&Id::new(Type::Builtin(BuiltinType::Block), key.block.clone()))?;
+        let definition = self.state_definition(block)?;
+
+        let mut state = 0;
+        for property in definition.properties() {
+            let value = key.properties.get(&property.name).and_then(|text| property.parse(text));
+            if let Some(local) = value.and_then(|value| definition.set(state, &property.name, &value)) {
+                state = local;
+            }
+        }
+        self.state_id(BlockState { block, state })
+    }
+
+    /// The block a state belongs to
+    pub fn block(&self, id: StateId) -> Option<&B> {
+        let state = self.block_state(id)?;
+        match self.get_by_index(&Type::Builtin(BuiltinType::Block), state.block)? {
+            Data::Builtin(BuiltinData::Block(block)) => Some(block),
+            _ => None,
+        }
+    }
+
+    /// Empty for `StateId::AIR` and unknown states
+    pub fn collision_shape(&self, id: StateId) -> Shape {
+        self.block(id).map_or_else(Shape::empty, |block| block.collision_shape(self, id))
+    }
+
+    /// Empty for `StateId::AIR` and unknown states
+    pub fn outline_shape(&self, id: StateId) -> Shape {
+        self.block(id).map_or_else(Shape::empty, |block| block.outline_shape(self, id))
+    }
+
+    pub fn get_property(&self, id: StateId, name: &str) -> Option<Value> {
+        let state = self.block_state(id)?;
+        self.state_definition(state.block)?.get(state.state, name)
+    }
+
+    /// The same block with one property changed
+    pub fn with_property(&self, id: StateId, name: &str, value: &Value) -> Option<StateId> {
+        let state = self.block_state(id)?;
+        let local = self.state_definition(state.block)?.set(state.state, name, value)?;
+        self.state_id(BlockState { block: state.block, state: local })
+    }
+
+    /// The same block with one property moved to its next value
+    pub fn cycle_property(&self, id: StateId, name: &str) -> Option<StateId> {
+        let state = self.block_state(id)?;
+        let local = self.state_definition(state.block)?.cycle(state.state, name)?;
+        self.state_id(BlockState { block: state.block, state: local })
+    }
+}
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> States for Registry<T, B, I, D> {
+    fn get_property(&self, id: StateId, name: &str) -> Option<Value> {
+        Registry::get_property(self, id, name)
+    }
+
+    fn with_property(&self, id: StateId, name: &str, value: &Value) -> Option<StateId> {
+        Registry::with_property(self, id, name, value)
+    }
+
+    fn cycle_property(&self, id: StateId, name: &str) -> Option<StateId> {
+        Registry::cycle_property(self, id, name)
+    }
+
+    fn default_block_state(&self, block: &Key) -> Option<StateId> {
+        self.default_state(&Id::new(Type::Builtin(BuiltinType::Block), block.clone()))
+    }
+
+    fn same_block(&self, a: StateId, b: StateId) -> bool {
+        self.block_state(a).map(|state| state.block) == self.block_state(b).map(|state| state.block)
+    }
+
+    fn create_block_entity(&self, id: StateId) -> Option<BlockEntity> {
+        self.block(id)?.create_block_entity()
+    }
+}
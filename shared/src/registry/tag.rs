@@ -0,0 +1,216 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use serde::Deserialize;
+
+use crate::resources::Vfs;
+use crate::types::{block::Block, item::Item, BuiltinType, Id, Key, KeyError, Type};
+
+use super::{loader::{load_definitions, LoadError}, Registry, RegistryError};
+
+/// A value inside a tag, either an entry or every member of another tag
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TagEntry {
+    Id(Key),
+    Tag(Key),
+}
+
+impl TagEntry {
+    /// Tags are written with a leading `#`, keys without a namespace fall back to `namespace`
+    pub fn parse_in(namespace: &str, value: &str) -> Result<Self, KeyError> {
+        match value.strip_prefix('#') {
+            Some(tag) => Ok(TagEntry::Tag(Key::parse_in(namespace, tag)?)),
+            None => Ok(TagEntry::Id(Key::parse_in(namespace, value)?)),
+        }
+    }
+}
+
+/// A tag described by an addon's `tags/<category>/<path>.toml`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagDefinition {
+    /// The tag to add to, defaults to the file's own key so addons can extend each other's tags
+    pub tag: Option<String>,
+    /// Drops everything previously added to the tag
+    #[serde(default)]
+    pub replace: bool,
+    pub values: Vec<String>,
+}
+
+impl TagDefinition {
+    pub fn qualify(&mut self, namespace: &str) -> Result<(), KeyError> {
+        if let Some(tag) = &mut self.tag {
+            *tag = Key::parse_in(namespace, tag)?.to_string();
+        }
+        for value in &mut self.values {
+            *value = match TagEntry::parse_in(namespace, value)? {
+                TagEntry::Id(key) => key.to_string(),
+                TagEntry::Tag(key) => format!("#{key}"),
+            };
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone + Debug + Eq + Hash + PartialEq, B: Block, I: Item, D> Registry<T, B, I, D> {
+    /// Adds entries to a tag, creating it if needed
+    #[profiling::function]
+    pub fn add_to_tag(&mut self, category: Type<T>, tag: Key, entries: Vec<TagEntry>) {
+        self.tags
+            .entry(category.clone())
+            .or_default()
+            .entry(tag)
+            .or_default()
+            .extend(entries);
+        self.resolve_tags(&category);
+    }
+
+    /// Removes everything from a tag, the tag itself still exists afterwards
+    pub fn clear_tag(&mut self, category: Type<T>, tag: Key) {
+        self.tags.entry(category.clone()).or_default().insert(tag, vec![]);
+        self.resolve_tags(&category);
+    }
+
+    /// Every entry key in a tag, including those of nested tags
+    pub fn tag(&self, category: &Type<T>, tag: &Key) -> Option<&HashSet<Key>> {
+        self.resolved_tags.get(category)?.get(tag)
+    }
+
+    pub fn is_tagged(&self, id: &Id<T>, tag: &Key) -> bool {
+        self.tag(&id.r#type, tag).is_some_and(|members| members.contains(&id.key))
+    }
+
+    /// Every tag in a category, with nested tags flattened
+    pub fn tags(&self, category: &Type<T>) -> Option<&HashMap<Key, HashSet<Key>>> {
+        self.resolved_tags.get(category)
+    }
+
+    /// Registers `<namespace>:tags/blocks/*.toml` and `tags/items/*.toml` from `vfs`
+    #[profiling::function]
+    pub fn load_addon_tags(&mut self, vfs: &Vfs, namespace: &str) -> Result<(), Vec<LoadError>> {
+        let mut errors = vec![];
+
+        for (dir, category) in [("tags/blocks", BuiltinType::Block), ("tags/items", BuiltinType::Item)] {
+            let definitions = load_definitions(vfs, namespace, dir, &mut errors, TagDefinition::qualify);
+            for (file, key, definition) in definitions {
+                let tag = match definition.tag.as_deref().map(Key::parse) {
+                    Some(Ok(tag)) => tag,
+                    Some(Err(e)) => {
+                        errors.push(LoadError::new(file, e));
+                        continue;
+                    }
+                    None => key,
+                };
+
+                let entries: Result<Vec<TagEntry>, KeyError> = definition
+                    .values
+                    .iter()
+                    .map(|value| TagEntry::parse_in(namespace, value))
+                    .collect();
+                let entries = match entries {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        errors.push(LoadError::new(file, e));
+                        continue;
+                    }
+                };
+
+                if definition.replace {
+                    self.clear_tag(Type::Builtin(category), tag.clone());
+                }
+                self.add_to_tag(Type::Builtin(category), tag, entries);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Flattens nested tags. Missing or cyclic nested tags are skipped here and reported by `validate`
+    #[profiling::function]
+    fn resolve_tags(&mut self, category: &Type<T>) {
+        let Some(tags) = self.tags.get(category) else { return };
+
+        let resolved = tags
+            .keys()
+            .map(|tag| {
+                let mut members = HashSet::new();
+                collect_tag(tags, tag, &mut HashSet::new(), &mut members);
+                (tag.clone(), members)
+            })
+            .collect();
+
+        self.resolved_tags.insert(category.clone(), resolved);
+    }
+
+    pub(super) fn validate_tags(&self, errors: &mut Vec<RegistryError<T>>) {
+        for (category, tags) in &self.tags {
+            for (tag, entries) in tags {
+                for entry in entries {
+                    match entry {
+                        TagEntry::Id(key) => {
+                            let id = Id::new(category.clone(), key.clone());
+                            if !self.contains(&id) {
+                                errors.push(RegistryError::Dangling {
+                                    from: Id::new(category.clone(), tag.clone()),
+                                    suggestions: self.suggestions(&id),
+                                    to: id,
+                                });
+                            }
+                        }
+                        TagEntry::Tag(nested) => {
+                            if !tags.contains_key(nested) {
+                                errors.push(RegistryError::MissingTag {
+                                    tag: Id::new(category.clone(), tag.clone()),
+                                    missing: Id::new(category.clone(), nested.clone()),
+                                });
+                            }
+                        }
+                    }
+                }
+
+                if contains_cycle(tags, tag, &mut vec![]) {
+                    errors.push(RegistryError::TagCycle(Id::new(category.clone(), tag.clone())));
+                }
+            }
+        }
+    }
+}
+
+fn collect_tag(tags: &HashMap<Key, Vec<TagEntry>>, tag: &Key, visited: &mut HashSet<Key>, members: &mut HashSet<Key>) {
+    if !visited.insert(tag.clone()) {
+        return;
+    }
+
+    for entry in tags.get(tag).into_iter().flatten() {
+        match entry {
+            TagEntry::Id(key) => {
+                members.insert(key.clone());
+            }
+            TagEntry::Tag(nested) => collect_tag(tags, nested, visited, members),
+        }
+    }
+}
+
+/// Whether `tag` can reach itself through nested tags
+fn contains_cycle(tags: &HashMap<Key, Vec<TagEntry>>, tag: &Key, path: &mut Vec<Key>) -> bool {
+    if path.first() == Some(tag) {
+        return true;
+    }
+    if path.contains(tag) {
+        return false;
+    }
+
+    path.push(tag.clone());
+    let cycle = tags.get(tag).into_iter().flatten().any(|entry| match entry {
+        TagEntry::Tag(nested) => contains_cycle(tags, nested, path),
+        TagEntry::Id(_) => false,
+    });
+    path.pop();
+
+    cycle
+}
@@ -0,0 +1,42 @@
+//! Blocks and items without behavior, for registries built in tests
+
+use crate::{
+    direction::Direction,
+    types::{
+        block::{Block, PathfindingState},
+        context::{BlockContext, Context},
+        item::{Hand, Item, Location, Target},
+    },
+};
+
+use super::Registry;
+
+pub(crate) type TestRegistry = Registry<u8, TestBlock, TestItem, ()>;
+
+pub(crate) struct TestBlock;
+
+impl Block for TestBlock {
+    fn on_random_update(&self, _ctx: &mut BlockContext, _visible: bool) {}
+    fn on_neighbor_update(&self, _ctx: &mut BlockContext, _from: Direction, _visible: bool) {}
+    fn on_scheduled_update(&self, _ctx: &mut BlockContext, _visible: bool) {}
+    fn on_place(&self, _ctx: &mut BlockContext) {}
+    fn on_destroy(&self, _ctx: &mut BlockContext) {}
+    fn on_interact(&self, _ctx: &mut BlockContext) {}
+    fn on_collision(&self, _ctx: &mut BlockContext) {}
+    fn can_place(&self, _ctx: &BlockContext) -> bool {
+        true
+    }
+    fn can_interact(&self, _ctx: &BlockContext) -> bool {
+        true
+    }
+    fn can_pathfind(&self, _ctx: &BlockContext) -> PathfindingState {
+        PathfindingState::Solid
+    }
+}
+
+pub(crate) struct TestItem;
+
+impl Item for TestItem {
+    fn on_use(&self, _ctx: &mut Context, _target: Target, _hand: Hand) {}
+    fn on_update(&self, _ctx: &mut Context, _location: Location) {}
+}
@@ -0,0 +1,173 @@
+use std::{ffi::OsString, fs::{self, File}, io::Write, path::{Path, PathBuf}};
+
+use super::{resolve, ResourceError, Result};
+
+/// Writes `data` to `path` atomically, keeping up to `backups` previous versions as `<file>.bak.1` (newest) to
+/// `<file>.bak.<backups>`
+///
+/// The data is written to a temporary file next to `path`, flushed to disk and renamed over the target, so a crash
+/// leaves either the old or the new file but never a partial one
+#[profiling::function]
+pub fn save_with_backups(path: impl AsRef<Path>, data: &[u8], backups: usize) -> Result<()> {
+    let path = resolve(path);
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).map(Path::to_path_buf);
+    if let Some(parent) = &parent {
+        fs::create_dir_all(parent).map_err(|e| ResourceError::io(parent, e))?;
+    }
+
+    let temp = sibling(&path, ".tmp")?;
+    if let Err(e) = write_synced(&temp, data) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    if backups > 0 && path.is_file() {
+        rotate(&path, backups)?;
+        let newest = backup_path(&path, 1)?;
+        fs::copy(&path, &newest).map_err(|e| ResourceError::io(&newest, e))?;
+    }
+
+    if let Err(e) = fs::rename(&temp, &path) {
+        let _ = fs::remove_file(&temp);
+        return Err(ResourceError::io(&path, e));
+    }
+    if let Some(parent) = parent {
+        sync_dir(&parent);
+    }
+    Ok(())
+}
+
+/// The existing backups of `path`, newest first
+#[profiling::function]
+pub fn backups(path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let path = resolve(path);
+    let mut backups = vec![];
+    for index in 1.. {
+        let backup = backup_path(&path, index)?;
+        if !backup.is_file() {
+            break;
+        }
+        backups.push(backup);
+    }
+    Ok(backups)
+}
+
+/// Atomically replaces `path` with its newest backup, returns `false` if there is none
+///
+/// The restored backup leaves the rotation, so calling this again steps further back in time
+#[profiling::function]
+pub fn restore_latest_backup(path: impl AsRef<Path>) -> Result<bool> {
+    let path = resolve(path);
+    let backups = backups(&path)?;
+    let Some(newest) = backups.first() else {
+        return Ok(false);
+    };
+
+    let data = fs::read(newest).map_err(|e| ResourceError::io(newest, e))?;
+    save_with_backups(&path, &data, 0)?;
+
+    for (index, backup) in backups.iter().enumerate().skip(1) {
+        fs::rename(backup, &backups[index - 1]).map_err(|e| ResourceError::io(backup, e))?;
+    }
+    if backups.len() == 1 {
+        fs::remove_file(newest).map_err(|e| ResourceError::io(newest, e))?;
+    }
+    Ok(true)
+}
+
+/// Shifts `<file>.bak.1..` up by one, dropping anything past `keep`
+fn rotate(path: &Path, keep: usize) -> Result<()> {
+    let existing = backups(path)?;
+    for backup in existing.iter().skip(keep - 1) {
+        fs::remove_file(backup).map_err(|e| ResourceError::io(backup, e))?;
+    }
+    for index in (1..=existing.len().min(keep - 1)).rev() {
+        let from = &existing[index - 1];
+        let to = backup_path(path, index + 1)?;
+        fs::rename(from, &to).map_err(|e| ResourceError::io(from, e))?;
+    }
+    Ok(())
+}
+
+fn write_synced(path: &Path, data: &[u8]) -> Result<()> {
+    let mut file = File::create(path).map_err(|e| ResourceError::io(path, e))?;
+    file.write_all(data).map_err(|e| ResourceError::io(path, e))?;
+    file.sync_all().map_err(|e| ResourceError::io(path, e))
+}
+
+/// Makes the rename itself durable. Not every platform can open directories, so this is best effort
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+fn backup_path(path: &Path, index: usize) -> Result<PathBuf> {
+    sibling(path, &format!(".bak.{index}"))
+}
+
+fn sibling(path: &Path, suffix: &str) -> Result<PathBuf> {
+    let Some(name) = path.file_name() else {
+        return Err(ResourceError::invalid_path(path, "Path has no file name"));
+    };
+    let mut name = OsString::from(name);
+    name.push(suffix);
+    Ok(path.with_file_name(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::test_dir::TempDir;
+
+    use super::*;
+
+    fn contents(paths: &[PathBuf]) -> Vec<String> {
+        paths.iter().map(|path| fs::read_to_string(path).unwrap()).collect()
+    }
+
+    fn save_all(path: &Path, versions: &[&str], keep: usize) {
+        for version in versions {
+            save_with_backups(path, version.as_bytes(), keep).unwrap();
+        }
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = TempDir::new("backup_rotation");
+        let path = dir.join("data.toml");
+
+        save_all(&path, &["1", "2", "3"], 0);
+        assert!(backups(&path).unwrap().is_empty());
+        assert!(!sibling(&path, ".tmp").unwrap().exists());
+
+        save_all(&path, &["4", "5"], 1);
+        assert_eq!(contents(&backups(&path).unwrap()), ["4"]);
+
+        save_all(&path, &["6", "7", "8", "9"], 3);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "9");
+        assert_eq!(contents(&backups(&path).unwrap()), ["8", "7", "6"]);
+
+        // Keeping fewer drops the oldest
+        save_all(&path, &["10"], 2);
+        assert_eq!(contents(&backups(&path).unwrap()), ["9", "8"]);
+        save_all(&path, &["11"], 1);
+        assert_eq!(contents(&backups(&path).unwrap()), ["10"]);
+    }
+
+    #[test]
+    fn restores() {
+        let dir = TempDir::new("backup_restores");
+        let path = dir.join("data.toml");
+        save_all(&path, &["1", "2", "3", "4"], 3);
+
+        for (current, left) in [("3", vec!["2", "1"]), ("2", vec!["1"]), ("1", vec![])] {
+            assert!(restore_latest_backup(&path).unwrap());
+            assert_eq!(fs::read_to_string(&path).unwrap(), current);
+            assert_eq!(contents(&backups(&path).unwrap()), left);
+        }
+
+        assert!(!restore_latest_backup(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1");
+        assert!(!restore_latest_backup(dir.join("missing.toml")).unwrap());
+    }
+}
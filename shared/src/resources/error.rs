@@ -0,0 +1,65 @@
+use std::{fmt, io, path::{Path, PathBuf}};
+
+/// A problem reading or writing a resource, always carrying the path involved
+#[derive(Debug)]
+pub enum ResourceError {
+    NotFound(PathBuf),
+    WrongType { path: PathBuf, expected: String },
+    Parse { path: PathBuf, message: String },
+    Io { path: PathBuf, error: io::Error },
+    InvalidPath { path: PathBuf, reason: String },
+}
+
+impl ResourceError {
+    /// Wraps an io error, promoting `ErrorKind::NotFound` to `ResourceError::NotFound`
+    pub fn io(path: impl Into<PathBuf>, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => ResourceError::NotFound(path.into()),
+            _ => ResourceError::Io { path: path.into(), error },
+        }
+    }
+
+    pub fn parse(path: impl Into<PathBuf>, message: impl fmt::Display) -> Self {
+        ResourceError::Parse { path: path.into(), message: message.to_string() }
+    }
+
+    pub fn invalid_path(path: impl Into<PathBuf>, reason: impl fmt::Display) -> Self {
+        ResourceError::InvalidPath { path: path.into(), reason: reason.to_string() }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            ResourceError::NotFound(path)
+            | ResourceError::WrongType { path, .. }
+            | ResourceError::Parse { path, .. }
+            | ResourceError::Io { path, .. }
+            | ResourceError::InvalidPath { path, .. } => path,
+        }
+    }
+
+    /// The problem without the path, for reports that print the path separately
+    pub fn message(&self) -> String {
+        match self {
+            ResourceError::NotFound(_) => "File not found".to_string(),
+            ResourceError::WrongType { expected, .. } => format!("File is not of the correct type {expected}"),
+            ResourceError::Parse { message, .. } => message.clone(),
+            ResourceError::Io { error, .. } => error.to_string(),
+            ResourceError::InvalidPath { reason, .. } => format!("Invalid path: {reason}"),
+        }
+    }
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path().display(), self.message())
+    }
+}
+
+impl std::error::Error for ResourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResourceError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::{io::{BufReader, Read}, fs::{File, self, ReadDir, DirEntry}, result, path::{PathBuf, Path}, sync::RwLock};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use self::{backup::{backups, restore_latest_backup, save_with_backups}, error::ResourceError, versioned::{load_or_default_versioned, load_versioned, save_versioned, Versioned}, vfs::{Vfs, ADDON_DIRECTORY, GAME_DIRECTORY}, watch::Watcher};
+
+pub mod backup;
+pub mod error;
+pub mod versioned;
+pub mod vfs;
+pub mod watch;
+
+#[cfg(test)]
+pub(crate) mod test_dir;
+
+pub type Result<T> = result::Result<T, ResourceError>;
+
+static ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Sets the directory relative paths are resolved against, typically the game's install directory
+pub fn set_root(path: impl Into<PathBuf>) {
+    *ROOT.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(path.into());
+}
+
+/// Sets the root at startup from a `--root <dir>` command line argument, or to the directory holding the executable
+/// if `marker` is next to it, as in an installed game. Otherwise the root stays the working directory, as under `cargo run`
+pub fn init_root(marker: impl AsRef<Path>) {
+    let from_args = std::env::args().skip_while(|arg| arg != "--root").nth(1).map(PathBuf::from);
+    let root = from_args.or_else(|| {
+        let dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+        dir.join(marker).exists().then_some(dir)
+    });
+    if let Some(root) = root {
+        set_root(root);
+    }
+}
+
+/// The directory relative paths are resolved against, the working directory unless the root was set
+pub fn root() -> PathBuf {
+    let root = ROOT.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    root.or_else(|| std::env::current_dir().ok()).unwrap_or_default()
+}
+
+/// Resolves `path` against `root`, absolute paths are kept as is
+pub fn resolve(path: impl AsRef<Path>) -> PathBuf {
+    root().join(path)
+}
+
+#[profiling::function]
+pub fn read(path: impl AsRef<Path>) -> Result<File> {
+    let path = resolve(path);
+    File::open(&path).map_err(|e| ResourceError::io(path, e))
+}
+
+#[profiling::function]
+pub fn read_bytes(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let path = resolve(path);
+    read_file(&path)
+}
+
+#[profiling::function]
+pub fn read_string(path: impl AsRef<Path>) -> Result<String> {
+    let path = resolve(path);
+    into_string(&path, read_file(&path)?)
+}
+
+#[profiling::function]
+pub fn read_dir(path: impl AsRef<Path>) -> Result<ReadDir> {
+    let path = resolve(path);
+    fs::read_dir(&path).map_err(|e| ResourceError::io(path, e))
+}
+
+#[profiling::function]
+pub fn read_dir_entry_bytes(entry: &DirEntry, file_type: Option<&str>) -> Result<Vec<u8>> {
+    let path = entry.path();
+
+    if let Some(file_type) = file_type {
+        type_match(&path, file_type)?;
+    }
+
+    read_file(&path)
+}
+
+#[profiling::function]
+pub fn read_dir_entry_string(entry: &DirEntry, file_type: Option<&str>) -> Result<String> {
+    let path = entry.path();
+
+    if let Some(file_type) = file_type {
+        type_match(&path, file_type)?;
+    }
+
+    into_string(&path, read_file(&path)?)
+}
+
+/// Checks that everything after the first `.` of the file name is `file_type`
+#[profiling::function]
+fn type_match(path: &Path, file_type: &str) -> Result<()> {
+    let Some(name) = path.file_name() else {
+        return Err(ResourceError::invalid_path(path, "Path has no file name"));
+    };
+    let Some(name) = name.to_str() else {
+        return Err(ResourceError::invalid_path(path, "File name is not valid UTF-8"));
+    };
+
+    match name.split_once('.') {
+        Some((_, extension)) if extension == file_type => Ok(()),
+        _ => Err(ResourceError::WrongType { path: path.to_path_buf(), expected: file_type.to_string() }),
+    }
+}
+
+/// Atomically writes `data` to `path`, creating missing parent directories
+#[profiling::function]
+pub fn save(path: impl AsRef<Path>, data: &[u8]) -> Result<()> {
+    save_with_backups(path, data, 0)
+}
+
+#[profiling::function]
+pub fn save_toml<T>(path: impl AsRef<Path>, data: T) -> Result<()> where T: Serialize {
+    let path = resolve(path);
+    save(&path, Format::Toml.encode(&path, &data)?.as_bytes())
+}
+
+#[profiling::function]
+pub fn save_json<T>(path: impl AsRef<Path>, data: T) -> Result<()> where T: Serialize {
+    let path = resolve(path);
+    save(&path, Format::Json.encode(&path, &data)?.as_bytes())
+}
+
+#[profiling::function]
+pub fn load_toml<T>(path: impl AsRef<Path>) -> Result<T> where T: DeserializeOwned {
+    let path = resolve(path);
+    let contents = into_string(&path, read_file(&path)?)?;
+    toml::from_str(&contents).map_err(|e| ResourceError::parse(path, e.message()))
+}
+
+#[profiling::function]
+pub fn load_json<T>(path: impl AsRef<Path>) -> Result<T> where T: DeserializeOwned {
+    let path = resolve(path);
+    let contents = read_file(&path)?;
+    serde_json::from_slice(&contents).map_err(|e| ResourceError::parse(path, e))
+}
+
+/// Loads a `.toml` or `.json` file, writing and returning `T::default()` if it doesn't exist yet
+#[profiling::function]
+pub fn load_or_default<T>(path: impl AsRef<Path>) -> Result<T> where T: Serialize + DeserializeOwned + Default {
+    let path = resolve(path);
+    let format = Format::of(&path)?;
+    match format.load(&path) {
+        Err(ResourceError::NotFound(_)) => {
+            let data = T::default();
+            format.save(&path, &data, 0)?;
+            Ok(data)
+        }
+        result => result,
+    }
+}
+
+/// A file format picked from the extension
+#[derive(Clone, Copy)]
+enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    fn of(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Ok(Format::Toml),
+            Some("json") => Ok(Format::Json),
+            _ => Err(ResourceError::WrongType { path: path.to_path_buf(), expected: "toml or json".to_string() }),
+        }
+    }
+
+    fn load<T: DeserializeOwned>(self, path: &Path) -> Result<T> {
+        match self {
+            Format::Toml => load_toml(path),
+            Format::Json => load_json(path),
+        }
+    }
+
+    fn encode<T: Serialize>(self, path: &Path, data: &T) -> Result<String> {
+        match self {
+            Format::Toml => toml::to_string(data).map_err(|e| ResourceError::parse(path, e)),
+            Format::Json => serde_json::to_string_pretty(data).map_err(|e| ResourceError::parse(path, e)),
+        }
+    }
+
+    fn save<T: Serialize>(self, path: &Path, data: &T, backups: usize) -> Result<()> {
+        save_with_backups(path, self.encode(path, data)?.as_bytes(), backups)
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let file = File::open(path).map_err(|e| ResourceError::io(path, e))?;
+    let mut contents = Vec::new();
+    BufReader::new(file).read_to_end(&mut contents).map_err(|e| ResourceError::io(path, e))?;
+    Ok(contents)
+}
+
+fn into_string(path: &Path, contents: Vec<u8>) -> Result<String> {
+    String::from_utf8(contents).map_err(|e| ResourceError::parse(path, e))
+}
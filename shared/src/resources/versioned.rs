@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{resolve, Format, ResourceError, Result};
+
+/// The field the schema version is stored in
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// A saved file whose layout can change between releases
+///
+/// Files are written with `SCHEMA_VERSION` and older files are upgraded one version at a time by `migrate` before being
+/// deserialized. Files without a version field are treated as version 0
+pub trait Versioned: Serialize + DeserializeOwned {
+    const SCHEMA_VERSION: u32;
+
+    /// Upgrades `data` written with schema `version` to `version + 1`
+    fn migrate(data: &mut toml::Table, version: u32) -> std::result::Result<(), String> {
+        let _ = data;
+        Err(format!("No migration from schema version {version}"))
+    }
+}
+
+/// Loads a `.toml` or `.json` file written by `save_versioned`, migrating it to the current schema
+#[profiling::function]
+pub fn load_versioned<T: Versioned>(path: impl AsRef<Path>) -> Result<T> {
+    let path = resolve(path);
+    let mut data: toml::Table = Format::of(&path)?.load(&path)?;
+
+    let version = match data.remove(SCHEMA_VERSION_FIELD) {
+        None => 0,
+        Some(toml::Value::Integer(version)) => u32::try_from(version)
+            .map_err(|_| ResourceError::parse(&path, format!("Invalid schema version {version}")))?,
+        Some(_) => return Err(ResourceError::parse(&path, "Schema version must be an integer")),
+    };
+    if version > T::SCHEMA_VERSION {
+        return Err(ResourceError::parse(
+            &path,
+            format!("Schema version {version} is newer than the supported version {}", T::SCHEMA_VERSION),
+        ));
+    }
+
+    for version in version..T::SCHEMA_VERSION {
+        T::migrate(&mut data, version).map_err(|message| ResourceError::parse(&path, message))?;
+    }
+
+    toml::Value::Table(data).try_into().map_err(|e: toml::de::Error| ResourceError::parse(path, e.message()))
+}
+
+/// Atomically saves `data` as `.toml` or `.json` tagged with the current schema version, keeping `backups` previous
+/// versions
+#[profiling::function]
+pub fn save_versioned<T: Versioned>(path: impl AsRef<Path>, data: &T, backups: usize) -> Result<()> {
+    let path = resolve(path);
+    let format = Format::of(&path)?;
+
+    let mut table = match toml::Value::try_from(data) {
+        Ok(toml::Value::Table(table)) => table,
+        Ok(_) => return Err(ResourceError::parse(&path, "Versioned data must serialize to a table")),
+        Err(e) => return Err(ResourceError::parse(&path, e)),
+    };
+    table.insert(SCHEMA_VERSION_FIELD.to_string(), toml::Value::Integer(T::SCHEMA_VERSION.into()));
+
+    format.save(&path, &table, backups)
+}
+
+/// Like `load_versioned`, but writes and returns `T::default()` if the file doesn't exist yet
+#[profiling::function]
+pub fn load_or_default_versioned<T: Versioned + Default>(path: impl AsRef<Path>) -> Result<T> {
+    match load_versioned(&path) {
+        Err(ResourceError::NotFound(_)) => {
+            let data = T::default();
+            save_versioned(path, &data, 0)?;
+            Ok(data)
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::resources::test_dir::TempDir;
+
+    use super::*;
+
+    /// Version 1 added `size`, version 2 renamed `velocity` to `speed`
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        name: String,
+        size: i64,
+        speed: i64,
+    }
+
+    impl Versioned for Settings {
+        const SCHEMA_VERSION: u32 = 2;
+
+        fn migrate(data: &mut toml::Table, version: u32) -> std::result::Result<(), String> {
+            match version {
+                0 => {
+                    data.insert("size".to_string(), toml::Value::Integer(1));
+                    Ok(())
+                }
+                1 => {
+                    let velocity = data.remove("velocity").ok_or("Missing velocity")?;
+                    data.insert("speed".to_string(), velocity);
+                    Ok(())
+                }
+                _ => Err(format!("No migration from schema version {version}")),
+            }
+        }
+    }
+
+    fn settings(size: i64) -> Settings {
+        Settings { name: "test".to_string(), size, speed: 5 }
+    }
+
+    #[test]
+    fn migrations() {
+        let dir = TempDir::new("versioned_migrations");
+        let load = |file: &str, contents: &str| {
+            dir.write(file, contents);
+            load_versioned::<Settings>(dir.join(file))
+        };
+
+        assert_eq!(load("v0.toml", "name = \"test\"\nvelocity = 5").unwrap(), settings(1));
+        assert_eq!(load("v1.toml", "schema_version = 1\nname = \"test\"\nsize = 3\nvelocity = 5").unwrap(), settings(3));
+        assert_eq!(load("v2.toml", "schema_version = 2\nname = \"test\"\nsize = 3\nspeed = 5").unwrap(), settings(3));
+        assert_eq!(load("v2.json", r#"{"schema_version": 2, "name": "test", "size": 3, "speed": 5}"#).unwrap(), settings(3));
+
+        assert!(matches!(load("v3.toml", "schema_version = 3"), Err(ResourceError::Parse { .. })));
+        assert!(matches!(load("broken.toml", "name = \"test\""), Err(ResourceError::Parse { .. })));
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = TempDir::new("versioned_round_trip");
+        let path = dir.join("settings.toml");
+        save_versioned(&path, &settings(3), 0).unwrap();
+
+        let saved: toml::Table = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.get(SCHEMA_VERSION_FIELD), Some(&toml::Value::Integer(2)));
+        assert_eq!(load_versioned::<Settings>(&path).unwrap(), settings(3));
+    }
+}
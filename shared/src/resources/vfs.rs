@@ -0,0 +1,403 @@
+use std::{collections::BTreeSet, fs::{self, File}, io::Read, path::{Path, PathBuf}, sync::Mutex};
+
+use zip::{result::ZipError, ZipArchive};
+
+use crate::types::Key;
+
+use super::{into_string, resolve, ResourceError, Result};
+
+/// The base game directory, holding one folder per namespace
+pub const GAME_DIRECTORY: &str = "game";
+/// Holds one folder or `.zip` archive per addon, named after its namespace
+pub const ADDON_DIRECTORY: &str = "addons";
+
+/// An ordered stack of resource sources addressed by logical `namespace:path` keys
+///
+/// Layers mounted later override earlier ones, so the base game directory is mounted first and addons on top
+#[derive(Default)]
+pub struct Vfs {
+    layers: Vec<Layer>,
+}
+
+struct Layer {
+    /// Layers mounted for a single namespace hold its files at their root, shared layers hold one folder per namespace
+    namespace: Option<String>,
+    source: Source,
+}
+
+enum Source {
+    Directory(PathBuf),
+    Archive {
+        path: PathBuf,
+        archive: Mutex<ZipArchive<File>>,
+        files: BTreeSet<String>,
+    },
+}
+
+impl Vfs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The base game directory with every addon in [`ADDON_DIRECTORY`] mounted on top.
+    /// Anything that fails to mount is logged and left out, so the game still starts without it
+    #[profiling::function]
+    pub fn game() -> Self {
+        let mut vfs = Self::new();
+        if let Err(e) = vfs.mount_directory(GAME_DIRECTORY) {
+            log::warn!("Failed to mount the base game: {e}");
+        }
+        if let Err(e) = vfs.mount_addons(ADDON_DIRECTORY) {
+            log::error!("Failed to mount addons: {e}");
+        }
+        vfs
+    }
+
+    /// Mounts a directory containing one folder per namespace, like the base game directory
+    #[profiling::function]
+    pub fn mount_directory(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let source = Source::directory(path)?;
+        self.layers.push(Layer { namespace: None, source });
+        Ok(())
+    }
+
+    /// Mounts a zip archive containing one folder per namespace
+    #[profiling::function]
+    pub fn mount_archive(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let source = Source::archive(path)?;
+        self.layers.push(Layer { namespace: None, source });
+        Ok(())
+    }
+
+    /// Mounts an addon folder or `.zip` archive whose files all belong to `namespace`
+    #[profiling::function]
+    pub fn mount_addon(&mut self, namespace: &str, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let source = if path.extension().is_some_and(|extension| extension == "zip") {
+            Source::archive(path)?
+        } else {
+            Source::directory(path)?
+        };
+        self.layers.push(Layer { namespace: Some(namespace.to_string()), source });
+        Ok(())
+    }
+
+    /// Mounts every folder and `.zip` archive in `directory` as an addon named after it, in order of their names.
+    /// Returns the namespaces mounted, a missing `directory` holds no addons. Addons that fail to mount are skipped with a warning
+    #[profiling::function]
+    pub fn mount_addons(&mut self, directory: impl AsRef<Path>) -> Result<Vec<String>> {
+        let directory = resolve(directory);
+        if !directory.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let entries = fs::read_dir(&directory).map_err(|e| ResourceError::io(&directory, e))?;
+        let mut paths: Vec<PathBuf> = entries.filter_map(|entry| Some(entry.ok()?.path())).collect();
+        paths.sort();
+
+        let mut namespaces = vec![];
+        for path in paths {
+            let is_archive = path.extension().is_some_and(|extension| extension == "zip");
+            if !path.is_dir() && !is_archive {
+                continue;
+            }
+            let name = if is_archive { path.file_stem() } else { path.file_name() };
+            let Some(namespace) = name.and_then(|name| name.to_str()) else {
+                log::warn!("Skipping addon {}: Name is not valid UTF-8", path.display());
+                continue;
+            };
+            if let Err(e) = Key::new(namespace, "addon") {
+                log::warn!("Skipping addon {}: {e}", path.display());
+                continue;
+            }
+            match self.mount_addon(namespace, &path) {
+                Ok(()) => namespaces.push(namespace.to_string()),
+                Err(e) => log::warn!("Skipping addon {}: {e}", path.display()),
+            }
+        }
+        Ok(namespaces)
+    }
+
+    /// Every namespace some layer provides files for, sorted and without duplicates
+    #[profiling::function]
+    pub fn namespaces(&self) -> Vec<String> {
+        let mut namespaces = BTreeSet::new();
+        for layer in &self.layers {
+            layer.namespaces(&mut namespaces);
+        }
+        namespaces.into_iter().collect()
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Whether any layer provides `path`
+    #[profiling::function]
+    pub fn exists(&self, path: &str) -> Result<bool> {
+        let key = parse(path)?;
+        Ok(self.layers.iter().rev().any(|layer| layer.contains(&key)))
+    }
+
+    /// The on-disk location of the layer providing `path`, mostly useful for error messages
+    #[profiling::function]
+    pub fn source_of(&self, path: &str) -> Result<Option<PathBuf>> {
+        let key = parse(path)?;
+        Ok(self.layers.iter().rev().find(|layer| layer.contains(&key)).map(|layer| layer.location(&key)))
+    }
+
+    /// The size in bytes of `path` in the topmost layer that provides it
+    #[profiling::function]
+    pub fn file_size(&self, path: &str) -> Result<u64> {
+        let key = parse(path)?;
+        for layer in self.layers.iter().rev() {
+            if let Some(size) = layer.size(&key)? {
+                return Ok(size);
+            }
+        }
+        Err(ResourceError::NotFound(PathBuf::from(path)))
+    }
+
+    /// Reads `path` from the topmost layer that provides it
+    #[profiling::function]
+    pub fn read_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let key = parse(path)?;
+        for layer in self.layers.iter().rev() {
+            if let Some(contents) = layer.read(&key)? {
+                return Ok(contents);
+            }
+        }
+        Err(ResourceError::NotFound(PathBuf::from(path)))
+    }
+
+    #[profiling::function]
+    pub fn read_string(&self, path: &str) -> Result<String> {
+        let contents = self.read_bytes(path)?;
+        into_string(Path::new(path), contents)
+    }
+
+    /// Lists the files directly inside the logical `directory` across all layers, sorted and without duplicates
+    ///
+    /// Files whose names aren't valid key paths are skipped with a warning, since they can't be addressed anyway
+    #[profiling::function]
+    pub fn list(&self, directory: &str) -> Result<Vec<Key>> {
+        let key = parse(directory)?;
+        let mut files = BTreeSet::new();
+        for layer in &self.layers {
+            layer.list(&key, &mut files)?;
+        }
+        Ok(files
+            .into_iter()
+            .filter_map(|path| match Key::new(key.namespace.clone(), path) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    log::warn!("Skipping a file in {directory}: {e}");
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+impl Layer {
+    /// The path of `key` relative to the layer root, or `None` if this layer doesn't serve its namespace
+    fn relative(&self, key: &Key) -> Option<String> {
+        match &self.namespace {
+            Some(namespace) if *namespace == key.namespace => Some(key.path.clone()),
+            Some(_) => None,
+            None => Some(format!("{}/{}", key.namespace, key.path)),
+        }
+    }
+
+    fn location(&self, key: &Key) -> PathBuf {
+        let relative = self.relative(key).unwrap_or_default();
+        match &self.source {
+            Source::Directory(root) => root.join(relative),
+            Source::Archive { path, .. } => path.join(relative),
+        }
+    }
+
+    fn contains(&self, key: &Key) -> bool {
+        let Some(relative) = self.relative(key) else {
+            return false;
+        };
+        match &self.source {
+            Source::Directory(root) => root.join(relative).is_file(),
+            Source::Archive { files, .. } => files.contains(&relative),
+        }
+    }
+
+    fn size(&self, key: &Key) -> Result<Option<u64>> {
+        let Some(relative) = self.relative(key) else {
+            return Ok(None);
+        };
+        match &self.source {
+            Source::Directory(root) => {
+                let file = root.join(relative);
+                if !file.is_file() {
+                    return Ok(None);
+                }
+                fs::metadata(&file).map(|metadata| Some(metadata.len())).map_err(|e| ResourceError::io(file, e))
+            }
+            Source::Archive { path, archive, files } => {
+                if !files.contains(&relative) {
+                    return Ok(None);
+                }
+                let mut archive = archive.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let entry = archive.by_name(&relative).map_err(|e| zip_error(&path.join(&relative), e))?;
+                Ok(Some(entry.size()))
+            }
+        }
+    }
+
+    fn read(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        let Some(relative) = self.relative(key) else {
+            return Ok(None);
+        };
+        match &self.source {
+            Source::Directory(root) => {
+                let file = root.join(relative);
+                if !file.is_file() {
+                    return Ok(None);
+                }
+                fs::read(&file).map(Some).map_err(|e| ResourceError::io(file, e))
+            }
+            Source::Archive { path, archive, files } => {
+                if !files.contains(&relative) {
+                    return Ok(None);
+                }
+                let location = path.join(&relative);
+                let mut archive = archive.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let mut entry = archive.by_name(&relative).map_err(|e| zip_error(&location, e))?;
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents).map_err(|e| ResourceError::io(&location, e))?;
+                Ok(Some(contents))
+            }
+        }
+    }
+
+    fn namespaces(&self, found: &mut BTreeSet<String>) {
+        if let Some(namespace) = &self.namespace {
+            found.insert(namespace.clone());
+            return;
+        }
+        match &self.source {
+            Source::Directory(root) => {
+                let Ok(entries) = fs::read_dir(root) else { return };
+                for entry in entries.filter_map(|entry| entry.ok()) {
+                    let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+                    if let (true, Some(name)) = (is_dir, entry.file_name().to_str()) {
+                        found.insert(name.to_string());
+                    }
+                }
+            }
+            Source::Archive { files, .. } => {
+                found.extend(files.iter().filter_map(|file| Some(file.split_once('/')?.0.to_string())));
+            }
+        }
+    }
+
+    fn list(&self, directory: &Key, found: &mut BTreeSet<String>) -> Result<()> {
+        let Some(relative) = self.relative(directory) else {
+            return Ok(());
+        };
+        match &self.source {
+            Source::Directory(root) => {
+                let dir = root.join(&relative);
+                if !dir.is_dir() {
+                    return Ok(());
+                }
+                let entries = fs::read_dir(&dir).map_err(|e| ResourceError::io(&dir, e))?;
+                for entry in entries.filter_map(|entry| entry.ok()) {
+                    let is_file = entry.file_type().is_ok_and(|file_type| file_type.is_file());
+                    if let (true, Some(name)) = (is_file, entry.file_name().to_str()) {
+                        found.insert(format!("{}/{}", directory.path, name));
+                    }
+                }
+            }
+            Source::Archive { files, .. } => {
+                let prefix = format!("{relative}/");
+                for file in files.iter().filter_map(|file| file.strip_prefix(&prefix)) {
+                    if !file.contains('/') {
+                        found.insert(format!("{}/{}", directory.path, file));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Source {
+    fn directory(path: impl AsRef<Path>) -> Result<Self> {
+        let root = resolve(&path);
+        if !root.is_dir() {
+            return Err(ResourceError::NotFound(root));
+        }
+        Ok(Source::Directory(root))
+    }
+
+    fn archive(path: impl AsRef<Path>) -> Result<Self> {
+        let path = resolve(&path);
+        let file = File::open(&path).map_err(|e| ResourceError::io(&path, e))?;
+        let archive = ZipArchive::new(file).map_err(|e| zip_error(&path, e))?;
+        let files = archive.file_names().filter(|name| !name.ends_with('/')).map(str::to_string).collect();
+        Ok(Source::Archive { path, archive: Mutex::new(archive), files })
+    }
+}
+
+/// Parses a logical path, rejecting anything that could escape a layer root
+fn parse(path: &str) -> Result<Key> {
+    let key = Key::parse(path).map_err(|e| ResourceError::invalid_path(path, e))?;
+    if key.path.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..") {
+        return Err(ResourceError::invalid_path(path, "Path segments must not be empty, \".\" or \"..\""));
+    }
+    Ok(key)
+}
+
+fn zip_error(path: &Path, error: ZipError) -> ResourceError {
+    match error {
+        ZipError::Io(e) => ResourceError::io(path, e),
+        ZipError::FileNotFound => ResourceError::NotFound(path.to_path_buf()),
+        e => ResourceError::parse(path, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::resources::test_dir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn addon_stack() {
+        let dir = TempDir::new("vfs_addon_stack");
+        dir.write("game/base/blocks/stone.toml", "base");
+        dir.write("game/base/blocks/dirt.toml", "base");
+        dir.write("addons/extra/blocks/lamp.toml", "extra");
+        dir.write("addons/notes.txt", "not an addon");
+
+        let mut zip = ZipWriter::new(File::create(dir.join("addons/base.zip")).unwrap());
+        zip.start_file("blocks/stone.toml", FileOptions::default()).unwrap();
+        zip.write_all(b"zipped").unwrap();
+        zip.finish().unwrap();
+
+        let mut vfs = Vfs::new();
+        vfs.mount_directory(dir.join("game")).unwrap();
+        let addons = vfs.mount_addons(dir.join("addons")).unwrap();
+        assert_eq!(addons, ["base", "extra"]);
+        assert_eq!(vfs.namespaces(), ["base", "extra"]);
+
+        // Addons override the base game, files they don't replace still come from below
+        assert_eq!(vfs.read_string("base:blocks/stone.toml").unwrap(), "zipped");
+        assert_eq!(vfs.read_string("base:blocks/dirt.toml").unwrap(), "base");
+        let files: Vec<_> = vfs.list("base:blocks").unwrap().iter().map(Key::to_string).collect();
+        assert_eq!(files, ["base:blocks/dirt.toml", "base:blocks/stone.toml"]);
+
+        assert_eq!(vfs.mount_addons(dir.join("missing")).unwrap(), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}, time::{Duration, Instant, SystemTime}};
+
+use super::resolve;
+
+/// Notices created, modified and removed files by periodically comparing modification times and sizes
+///
+/// Polling is slower than native file events but behaves the same on every platform and filesystem
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    extensions: Vec<String>,
+    interval: Duration,
+    last_scan: Instant,
+    snapshot: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl Watcher {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            paths: vec![],
+            extensions: vec![],
+            interval,
+            last_scan: Instant::now(),
+            snapshot: BTreeMap::new(),
+        }
+    }
+
+    /// Only reports files with one of `extensions`, all files are reported if this is never called
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions.iter().map(|extension| extension.to_string()).collect();
+        self
+    }
+
+    /// Starts watching a file or directory tree, its current contents are not reported as changes
+    #[profiling::function]
+    pub fn watch(&mut self, path: impl AsRef<Path>) {
+        let path = resolve(path);
+        let mut files = BTreeMap::new();
+        self.collect(&path, &mut files);
+        self.snapshot.extend(files);
+        self.paths.push(path);
+    }
+
+    /// Scans the watched paths if the polling interval has passed since the last scan
+    #[profiling::function]
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.last_scan.elapsed() < self.interval {
+            return vec![];
+        }
+        self.scan()
+    }
+
+    /// Scans the watched paths now, returning every file that was created, modified or removed since the last scan
+    #[profiling::function]
+    pub fn scan(&mut self) -> Vec<PathBuf> {
+        self.last_scan = Instant::now();
+
+        let mut files = BTreeMap::new();
+        for path in &self.paths {
+            self.collect(path, &mut files);
+        }
+
+        let mut changed: Vec<PathBuf> = files
+            .iter()
+            .filter(|(file, stamp)| self.snapshot.get(*file) != Some(stamp))
+            .map(|(file, _)| file.clone())
+            .collect();
+        changed.extend(self.snapshot.keys().filter(|file| !files.contains_key(*file)).cloned());
+        changed.sort();
+
+        self.snapshot = files;
+        changed
+    }
+
+    fn collect(&self, path: &Path, files: &mut BTreeMap<PathBuf, (Option<SystemTime>, u64)>) {
+        let Ok(metadata) = fs::metadata(path) else {
+            return;
+        };
+
+        if metadata.is_dir() {
+            let Ok(entries) = fs::read_dir(path) else {
+                return;
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                self.collect(&entry.path(), files);
+            }
+        } else if self.matches(path) {
+            files.insert(path.to_path_buf(), (metadata.modified().ok(), metadata.len()));
+        }
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        self.extensions.is_empty()
+            || path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| self.extensions.iter().any(|e| e == extension))
+    }
+}
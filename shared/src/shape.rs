@@ -0,0 +1,210 @@
+use glam::{IVec3, Vec3};
+
+use crate::{
+    direction::{BlockTransform, Direction},
+    types::{context::WorldAccess, state::StateId},
+    util::BoundingBox,
+};
+
+/// Boxes in block local coordinates, where `0..1` on every axis is one whole block
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Shape {
+    boxes: Vec<BoundingBox>,
+}
+
+impl Shape {
+    pub fn new(boxes: Vec<BoundingBox>) -> Self {
+        Self { boxes }
+    }
+
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn full() -> Self {
+        Self::new(vec![BoundingBox::FULL])
+    }
+
+    /// A single box given in sixteenths of a block, the way textures measure it
+    pub fn pixels(min: Vec3, max: Vec3) -> Self {
+        Self::new(vec![BoundingBox::new(min / 16.0, max / 16.0)])
+    }
+
+    /// Both shapes together
+    pub fn union(mut self, other: Shape) -> Self {
+        self.boxes.extend(other.boxes);
+        self
+    }
+
+    pub fn boxes(&self) -> &[BoundingBox] {
+        &self.boxes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.boxes.contains(&BoundingBox::FULL)
+    }
+
+    pub fn offset(&self, by: Vec3) -> Self {
+        Self::new(self.boxes.iter().map(|bounds| bounds.offset(by)).collect())
+    }
+
+    /// The same shape for a rotated or mirrored block
+    pub fn transform(&self, transform: &BlockTransform) -> Self {
+        Self::new(self.boxes.iter().map(|bounds| transform.apply_box(bounds)).collect())
+    }
+
+    /// The closest box a ray enters, as a distance in multiples of `direction` and the face it enters through
+    pub fn ray(&self, origin: Vec3, direction: Vec3) -> Option<(f32, Direction)> {
+        self.boxes
+            .iter()
+            .filter_map(|bounds| bounds.ray(origin, direction))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(distance, normal)| (distance, face(normal)))
+    }
+
+    /// The first box the `moving` box touches, as a fraction of `velocity` and the face it touches
+    pub fn sweep(&self, moving: &BoundingBox, velocity: Vec3) -> Option<(f32, Direction)> {
+        self.boxes
+            .iter()
+            .filter_map(|bounds| bounds.sweep(moving, velocity))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(time, normal)| (time, face(normal)))
+    }
+}
+
+/// The direction a unit normal points in
+fn face(normal: Vec3) -> Direction {
+    Direction::from_normal(normal.round().as_ivec3()).unwrap_or(Direction::UP)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub position: IVec3,
+    pub face: Direction,
+    pub distance: f32,
+}
+
+/// The first block shape along a ray, walking block by block up to `max_distance`.
+/// `shape` gives the shape of a state, such as `Registry::outline_shape` for picking
+#[profiling::function]
+pub fn raycast(
+    world: &dyn WorldAccess,
+    shape: impl Fn(StateId) -> Shape,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+) -> Option<RayHit> {
+    let direction = direction.try_normalize()?;
+    // Not `signum`, which is 1 for 0 and -1 for -0
+    let step = IVec3::from_array(std::array::from_fn(|axis| {
+        if direction[axis] > 0.0 {
+            1
+        } else if direction[axis] < 0.0 {
+            -1
+        } else {
+            0
+        }
+    }));
+    let delta = direction.recip().abs();
+
+    let mut position = origin.floor().as_ivec3();
+    let mut next = Vec3::ZERO;
+    for axis in 0..3 {
+        let boundary = if step[axis] > 0 { position[axis] as f32 + 1.0 } else { position[axis] as f32 };
+        next[axis] = if step[axis] == 0 { f32::INFINITY } else { (boundary - origin[axis]).abs() * delta[axis] };
+    }
+
+    let mut travelled = 0.0;
+    while travelled <= max_distance {
+        let state = world.get_state(position);
+        if state != StateId::AIR {
+            let hit = shape(state).offset(position.as_vec3()).ray(origin, direction);
+            if let Some((distance, face)) = hit.filter(|(distance, _)| *distance <= max_distance) {
+                return Some(RayHit { position, face, distance });
+            }
+        }
+
+        let axis = if next.x < next.y && next.x < next.z {
+            0
+        } else if next.y < next.z {
+            1
+        } else {
+            2
+        };
+        travelled = next[axis];
+        next[axis] += delta[axis];
+        position[axis] += step[axis];
+    }
+
+    None
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Movement {
+    /// How far the box actually moved
+    pub motion: Vec3,
+    /// Blocks the box ran into, in the order they stopped it
+    pub touched: Vec<IVec3>,
+    pub on_ground: bool,
+}
+
+/// Moves `bounds` by `velocity` until it runs into block shapes, one axis at a time with the vertical axis first.
+/// `shape` gives the shape of a state, such as `Registry::collision_shape`.
+/// Shapes may reach up to one block above their own position, like fences and walls do
+#[profiling::function]
+pub fn sweep(world: &dyn WorldAccess, shape: impl Fn(StateId) -> Shape, bounds: BoundingBox, velocity: Vec3) -> Movement {
+    let area = bounds.expand(velocity);
+    // The block below can stick up into the area
+    let min = area.min().floor().as_ivec3() - IVec3::Y;
+    let max = area.max().ceil().as_ivec3();
+
+    let mut shapes = vec![];
+    for x in min.x..max.x {
+        for y in min.y..max.y {
+            for z in min.z..max.z {
+                let position = IVec3::new(x, y, z);
+                let state = world.get_state(position);
+                if state != StateId::AIR {
+                    shapes.push((position, shape(state).offset(position.as_vec3())));
+                }
+            }
+        }
+    }
+
+    let mut movement = Movement::default();
+    let mut current = bounds;
+    for axis in [1, 0, 2] {
+        if velocity[axis] == 0.0 {
+            continue;
+        }
+        let mut axis_velocity = Vec3::ZERO;
+        axis_velocity[axis] = velocity[axis];
+
+        let mut time = 1.0;
+        let mut touched = vec![];
+        for (position, shape) in &shapes {
+            let Some((hit, _)) = shape.sweep(&current, axis_velocity) else { continue };
+            if hit < time {
+                time = hit;
+                touched.clear();
+            }
+            if hit <= time {
+                touched.push(*position);
+            }
+        }
+
+        let motion = axis_velocity * time;
+        current = current.offset(motion);
+        movement.motion += motion;
+        movement.touched.extend(touched);
+        if axis == 1 && velocity.y < 0.0 && time < 1.0 {
+            movement.on_ground = true;
+        }
+    }
+
+    movement
+}
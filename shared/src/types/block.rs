@@ -1,15 +1,51 @@
+use serde::Deserialize;
+
+use crate::{direction::Direction, shape::Shape};
+
+use super::{
+    block_entity::BlockEntity,
+    context::{BlockContext, States},
+    state::{Property, StateId},
+    Key, KeyError,
+};
+
+/// A block type shared by every position it is placed at, per position data lives in the world
 pub trait Block {
-    type Args;
+    fn on_random_update(&self, ctx: &mut BlockContext, visible: bool);
+    /// `from` is the face of this block that the changed neighbor touches
+    fn on_neighbor_update(&self, ctx: &mut BlockContext, from: Direction, visible: bool);
+    /// Called back once a tick requested through `Context::schedule` is due
+    fn on_scheduled_update(&self, ctx: &mut BlockContext, visible: bool);
+    fn on_place(&self, ctx: &mut BlockContext);
+    fn on_destroy(&self, ctx: &mut BlockContext);
+    fn on_interact(&self, ctx: &mut BlockContext);
+    fn on_collision(&self, ctx: &mut BlockContext);
+    fn can_place(&self, ctx: &BlockContext) -> bool;
+    fn can_interact(&self, ctx: &BlockContext) -> bool;
+    fn can_pathfind(&self, ctx: &BlockContext) -> PathfindingState;
+
+    /// Properties whose combinations make up this block's states, a block without any has one state
+    fn properties(&self) -> Vec<Property> {
+        Vec::new()
+    }
+
+    /// Boxes entities run into, `Block::on_collision` fires when they do
+    fn collision_shape(&self, _states: &dyn States, _state: StateId) -> Shape {
+        Shape::full()
+    }
+
+    /// Boxes used for picking and the selection outline
+    fn outline_shape(&self, states: &dyn States, state: StateId) -> Shape {
+        self.collision_shape(states, state)
+    }
 
-    fn on_random_update(&mut self, args: Self::Args, visible: bool);
-    fn on_neighbor_update(&mut self, args: Self::Args, visible: bool);
-    fn on_place(&mut self, args: Self::Args);
-    fn on_destroy(&mut self, args: Self::Args);
-    fn on_interact(&mut self, args: Self::Args);
-    fn on_collision(&mut self, args: Self::Args);
-    fn can_place(&self, args: Self::Args) -> bool;
-    fn can_interact(&self, args: Self::Args) -> bool;
-    fn can_pathfind(&self, args: Self::Args) -> PathfindingState;
+    /// Blocks that keep extra data per position return its initial value, it is created whenever the block is placed
+    fn create_block_entity(&self) -> Option<BlockEntity> {
+        None
+    }
+
+    /// Called every tick for each of this block's entities within `SimulationDistance`
+    fn on_block_entity_tick(&self, _ctx: &mut BlockContext, _entity: &mut BlockEntity) {}
 }
 
 pub enum PathfindingState {
@@ -17,3 +53,85 @@ pub enum PathfindingState {
     Dangerous,
     Empty,
 }
+
+/// A block described by an addon's `blocks/<path>.toml`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockDefinition {
+    pub display_name: String,
+    #[serde(default)]
+    pub textures: BlockTextures,
+    #[serde(default = "default_hardness")]
+    pub hardness: f32,
+    #[serde(default)]
+    pub light_emission: u8,
+    #[serde(default = "default_collision")]
+    pub collision: bool,
+}
+
+impl BlockDefinition {
+    pub fn qualify(&mut self, namespace: &str) -> Result<(), KeyError> {
+        self.textures.qualify(namespace)
+    }
+}
+
+fn default_hardness() -> f32 {
+    1.0
+}
+
+fn default_collision() -> bool {
+    true
+}
+
+/// Texture keys per face. A face falls back to `side` (horizontal faces only) and then `all`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockTextures {
+    pub all: Option<String>,
+    pub side: Option<String>,
+    pub up: Option<String>,
+    pub down: Option<String>,
+    pub north: Option<String>,
+    pub south: Option<String>,
+    pub east: Option<String>,
+    pub west: Option<String>,
+}
+
+impl BlockTextures {
+    pub fn get(&self, direction: Direction) -> Option<&str> {
+        let face = match direction {
+            Direction::UP => &self.up,
+            Direction::DOWN => &self.down,
+            Direction::NORTH => &self.north,
+            Direction::SOUTH => &self.south,
+            Direction::EAST => &self.east,
+            Direction::WEST => &self.west,
+        };
+        let side = match direction {
+            Direction::UP | Direction::DOWN => &None,
+            _ => &self.side,
+        };
+
+        face.as_ref().or(side.as_ref()).or(self.all.as_ref()).map(String::as_str)
+    }
+
+    /// Prefixes texture names that have no namespace with `namespace`
+    pub fn qualify(&mut self, namespace: &str) -> Result<(), KeyError> {
+        for texture in [
+            &mut self.all,
+            &mut self.side,
+            &mut self.up,
+            &mut self.down,
+            &mut self.north,
+            &mut self.south,
+            &mut self.east,
+            &mut self.west,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *texture = Key::parse_in(namespace, texture)?.to_string();
+        }
+        Ok(())
+    }
+}
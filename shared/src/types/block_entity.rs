@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use serde::{ser, Deserialize, Serialize, Serializer};
+
+/// Data attached to a single block position, such as a chest's contents or a sign's text.
+/// Fields marked as synced are sent to clients whenever they change
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(try_from = "SavedBlockEntity")]
+pub struct BlockEntity {
+    data: toml::Table,
+    synced: HashSet<String>,
+    dirty: bool,
+}
+
+/// The data is kept as TOML text, so non self-describing formats like the chunk format can store it
+#[derive(Serialize, Deserialize)]
+struct SavedBlockEntity {
+    data: String,
+    synced: Vec<String>,
+}
+
+impl TryFrom<&BlockEntity> for SavedBlockEntity {
+    type Error = toml::ser::Error;
+
+    fn try_from(entity: &BlockEntity) -> Result<Self, Self::Error> {
+        let mut synced: Vec<String> = entity.synced.iter().cloned().collect();
+        synced.sort();
+        Ok(Self {
+            data: toml::to_string(&entity.data)?,
+            synced,
+        })
+    }
+}
+
+/// Fails instead of saving empty data when the fields can't be written as TOML
+impl Serialize for BlockEntity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SavedBlockEntity::try_from(self).map_err(ser::Error::custom)?.serialize(serializer)
+    }
+}
+
+impl TryFrom<SavedBlockEntity> for BlockEntity {
+    type Error = toml::de::Error;
+
+    /// Entities with synced fields start out dirty, so clients learn about them once their chunk is loaded
+    fn try_from(saved: SavedBlockEntity) -> Result<Self, Self::Error> {
+        Ok(Self {
+            data: toml::from_str(&saved.data)?,
+            dirty: !saved.synced.is_empty(),
+            synced: saved.synced.into_iter().collect(),
+        })
+    }
+}
+
+impl BlockEntity {
+    pub fn new(data: toml::Table) -> Self {
+        Self {
+            data,
+            synced: HashSet::new(),
+            dirty: true,
+        }
+    }
+
+    /// Marks fields that clients need to see
+    pub fn with_synced<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.synced.extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn get(&self, field: &str) -> Option<&toml::Value> {
+        self.data.get(field)
+    }
+
+    pub fn set(&mut self, field: impl Into<String>, value: impl Into<toml::Value>) -> Option<toml::Value> {
+        let field = field.into();
+        self.dirty |= self.synced.contains(&field);
+        self.data.insert(field, value.into())
+    }
+
+    pub fn remove(&mut self, field: &str) -> Option<toml::Value> {
+        self.dirty |= self.synced.contains(field);
+        self.data.remove(field)
+    }
+
+    pub fn data(&self) -> &toml::Table {
+        &self.data
+    }
+
+    /// Only the fields clients see
+    pub fn synced_data(&self) -> toml::Table {
+        self.data
+            .iter()
+            .filter(|(field, _)| self.synced.contains(*field))
+            .map(|(field, value)| (field.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Whether a synced field changed since the last `take_dirty`
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+}
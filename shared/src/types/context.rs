@@ -0,0 +1,180 @@
+use std::ops::{Deref, DerefMut};
+
+use glam::IVec3;
+use rand::RngCore;
+
+use super::{
+    block_entity::BlockEntity,
+    state::{StateId, Value},
+    Key,
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct EntityId(pub u64);
+
+/// Block storage the engine exposes to callbacks
+pub trait WorldAccess {
+    /// Unloaded positions read as `StateId::AIR`
+    fn get_state(&self, position: IVec3) -> StateId;
+    /// Returns the previous state, writes to unloaded positions are ignored
+    fn set_state(&mut self, position: IVec3, state: StateId) -> StateId;
+    fn is_loaded(&self, position: IVec3) -> bool;
+    fn block_entity(&self, position: IVec3) -> Option<&BlockEntity>;
+    fn block_entity_mut(&mut self, position: IVec3) -> Option<&mut BlockEntity>;
+    /// Returns the previous entity, `None` removes it
+    fn set_block_entity(&mut self, position: IVec3, entity: Option<BlockEntity>) -> Option<BlockEntity>;
+}
+
+/// Block state lookups, implemented by the registry
+pub trait States {
+    fn get_property(&self, id: StateId, name: &str) -> Option<Value>;
+    fn with_property(&self, id: StateId, name: &str, value: &Value) -> Option<StateId>;
+    fn cycle_property(&self, id: StateId, name: &str) -> Option<StateId>;
+    fn default_block_state(&self, block: &Key) -> Option<StateId>;
+    fn same_block(&self, a: StateId, b: StateId) -> bool;
+    /// A fresh entity for the block a state belongs to, if it has one
+    fn create_block_entity(&self, id: StateId) -> Option<BlockEntity>;
+}
+
+/// Follow-up work requested by a callback, processed by the engine once the callback returns
+#[derive(Clone, Debug, PartialEq)]
+pub enum Update {
+    /// Notify the six neighbors of a position that it changed
+    Neighbors(IVec3),
+    /// Call the block at a position back after a number of ticks
+    Scheduled {
+        position: IVec3,
+        /// The state at `position` when the tick was requested, the tick is dropped if its block is gone by then
+        state: StateId,
+        delay: u32,
+        priority: i32,
+    },
+}
+
+pub struct Context<'a> {
+    pub world: &'a mut dyn WorldAccess,
+    pub states: &'a dyn States,
+    pub random: &'a mut dyn RngCore,
+    /// Whoever caused this callback, if anyone
+    pub entity: Option<EntityId>,
+    pub updates: &'a mut Vec<Update>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(
+        world: &'a mut dyn WorldAccess,
+        states: &'a dyn States,
+        random: &'a mut dyn RngCore,
+        entity: Option<EntityId>,
+        updates: &'a mut Vec<Update>,
+    ) -> Self {
+        Self {
+            world,
+            states,
+            random,
+            entity,
+            updates,
+        }
+    }
+
+    pub fn get_state(&self, position: IVec3) -> StateId {
+        self.world.get_state(position)
+    }
+
+    /// Changes a block and notifies its neighbors. Replacing it with a different block also replaces its entity.
+    /// Use `world` directly to skip both
+    pub fn set_state(&mut self, position: IVec3, state: StateId) -> StateId {
+        let previous = self.world.set_state(position, state);
+        if !self.states.same_block(previous, state) {
+            let entity = self.states.create_block_entity(state);
+            self.world.set_block_entity(position, entity);
+        }
+        if previous != state {
+            self.notify_neighbors(position);
+        }
+        previous
+    }
+
+    pub fn notify_neighbors(&mut self, position: IVec3) {
+        self.updates.push(Update::Neighbors(position));
+    }
+
+    /// Lower priorities run first when several updates are due on the same tick
+    pub fn schedule(&mut self, position: IVec3, delay: u32, priority: i32) {
+        self.updates.push(Update::Scheduled {
+            position,
+            state: self.world.get_state(position),
+            delay,
+            priority,
+        });
+    }
+}
+
+/// A `Context` for the block at `position`
+pub struct BlockContext<'a> {
+    pub position: IVec3,
+    pub state: StateId,
+    pub context: Context<'a>,
+}
+
+impl<'a> BlockContext<'a> {
+    pub fn new(position: IVec3, context: Context<'a>) -> Self {
+        Self {
+            position,
+            state: context.get_state(position),
+            context,
+        }
+    }
+
+    pub fn get_property(&self, name: &str) -> Option<Value> {
+        self.states.get_property(self.state, name)
+    }
+
+    /// Changes a property of this block in the world, returns whether the property and value exist
+    pub fn set_property(&mut self, name: &str, value: &Value) -> bool {
+        let Some(state) = self.states.with_property(self.state, name, value) else { return false };
+        self.replace(state);
+        true
+    }
+
+    /// Moves a property of this block to its next value in the world, returns whether the property exists
+    pub fn cycle_property(&mut self, name: &str) -> bool {
+        let Some(state) = self.states.cycle_property(self.state, name) else { return false };
+        self.replace(state);
+        true
+    }
+
+    /// Changes this block's state in the world and notifies its neighbors
+    pub fn replace(&mut self, state: StateId) {
+        let position = self.position;
+        self.context.set_state(position, state);
+        self.state = state;
+    }
+
+    pub fn block_entity(&self) -> Option<&BlockEntity> {
+        self.world.block_entity(self.position)
+    }
+
+    pub fn block_entity_mut(&mut self) -> Option<&mut BlockEntity> {
+        self.context.world.block_entity_mut(self.position)
+    }
+
+    pub fn schedule_self(&mut self, delay: u32) {
+        let position = self.position;
+        self.context.schedule(position, delay, 0);
+    }
+}
+
+impl<'a> Deref for BlockContext<'a> {
+    type Target = Context<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.context
+    }
+}
+
+impl<'a> DerefMut for BlockContext<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.context
+    }
+}
@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    item::{Hand, Location},
+    Key,
+};
+
+pub const HOTBAR_SIZE: usize = 9;
+pub const MAIN_SIZE: usize = 27;
+
+/// Some amount of one item. Stacks only merge when both their item and data match
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: Key,
+    pub count: u16,
+    pub max_stack_size: u16,
+    /// Anything specific to this stack, such as durability or a custom name
+    #[serde(default)]
+    pub data: toml::Table,
+}
+
+impl ItemStack {
+    pub fn new(item: Key, count: u16, max_stack_size: u16) -> Self {
+        let max_stack_size = max_stack_size.max(1);
+        Self {
+            item,
+            count: count.min(max_stack_size),
+            max_stack_size,
+            data: toml::Table::new(),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.count >= self.max_stack_size
+    }
+
+    /// How many more items fit
+    pub fn space(&self) -> u16 {
+        self.max_stack_size.saturating_sub(self.count)
+    }
+
+    pub fn can_merge(&self, other: &ItemStack) -> bool {
+        self.item == other.item && self.data == other.data
+    }
+
+    /// Moves as much of `other` into this stack as fits, returns how many items moved
+    pub fn merge(&mut self, other: &mut ItemStack) -> u16 {
+        if !self.can_merge(other) {
+            return 0;
+        }
+        let moved = self.space().min(other.count);
+        self.count += moved;
+        other.count -= moved;
+        moved
+    }
+
+    /// Takes up to `count` items off into a new stack, `None` if nothing was taken
+    pub fn split(&mut self, count: u16) -> Option<ItemStack> {
+        let count = count.min(self.count);
+        if count == 0 {
+            return None;
+        }
+        self.count -= count;
+        Some(ItemStack {
+            count,
+            ..self.clone()
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Slot {
+    Hotbar(usize),
+    OffHand,
+    Main(usize),
+}
+
+/// A hotbar, an off-hand slot and a main grid. The selected hotbar slot is the main hand
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    hotbar: [Option<ItemStack>; HOTBAR_SIZE],
+    off_hand: Option<ItemStack>,
+    main: [Option<ItemStack>; MAIN_SIZE],
+    selected: usize,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Out of range hotbar slots are ignored
+    pub fn select(&mut self, slot: usize) {
+        if slot < HOTBAR_SIZE {
+            self.selected = slot;
+        }
+    }
+
+    pub fn hand(&self, hand: Hand) -> Option<&ItemStack> {
+        match hand {
+            Hand::MainHand => self.get(Slot::Hotbar(self.selected)),
+            Hand::OffHand => self.get(Slot::OffHand),
+        }
+    }
+
+    /// Where a slot is, as far as `Item::on_update` is concerned
+    pub fn location(&self, slot: Slot) -> Location {
+        match slot {
+            Slot::Hotbar(index) if index == self.selected => Location::Hand(Hand::MainHand),
+            Slot::Hotbar(_) => Location::Hotbar,
+            Slot::OffHand => Location::Hand(Hand::OffHand),
+            Slot::Main(_) => Location::Inventory,
+        }
+    }
+
+    fn slot(&self, slot: Slot) -> Option<&Option<ItemStack>> {
+        match slot {
+            Slot::Hotbar(index) => self.hotbar.get(index),
+            Slot::OffHand => Some(&self.off_hand),
+            Slot::Main(index) => self.main.get(index),
+        }
+    }
+
+    fn slot_mut(&mut self, slot: Slot) -> Option<&mut Option<ItemStack>> {
+        match slot {
+            Slot::Hotbar(index) => self.hotbar.get_mut(index),
+            Slot::OffHand => Some(&mut self.off_hand),
+            Slot::Main(index) => self.main.get_mut(index),
+        }
+    }
+
+    pub fn get(&self, slot: Slot) -> Option<&ItemStack> {
+        self.slot(slot)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, slot: Slot) -> Option<&mut ItemStack> {
+        self.slot_mut(slot)?.as_mut()
+    }
+
+    /// Puts a stack into a slot, returning what was there. Out of range slots hand the stack back
+    pub fn set(&mut self, slot: Slot, stack: Option<ItemStack>) -> Option<ItemStack> {
+        match self.slot_mut(slot) {
+            Some(current) => std::mem::replace(current, stack.filter(|stack| !stack.is_empty())),
+            None => stack,
+        }
+    }
+
+    pub fn take(&mut self, slot: Slot) -> Option<ItemStack> {
+        self.slot_mut(slot)?.take()
+    }
+
+    pub fn swap(&mut self, a: Slot, b: Slot) {
+        if self.slot(a).is_none() || self.slot(b).is_none() {
+            return;
+        }
+        let stack = self.take(a);
+        let stack = self.set(b, stack);
+        self.set(a, stack);
+    }
+
+    /// Takes up to `count` items out of a slot, emptying it if nothing is left
+    pub fn split(&mut self, slot: Slot, count: u16) -> Option<ItemStack> {
+        let current = self.slot_mut(slot)?;
+        let split = current.as_mut()?.split(count);
+        if current.as_ref().is_some_and(ItemStack::is_empty) {
+            *current = None;
+        }
+        split
+    }
+
+    /// Moves as much of one slot into another as fits, moving the whole stack if `to` is empty.
+    /// Returns how many items moved
+    pub fn merge(&mut self, from: Slot, to: Slot) -> u16 {
+        if from == to || self.slot(to).is_none() {
+            return 0;
+        }
+        let Some(mut stack) = self.take(from) else { return 0 };
+
+        let moved = match self.get_mut(to) {
+            Some(target) => target.merge(&mut stack),
+            None => {
+                let count = stack.count;
+                self.set(to, Some(stack.clone()));
+                stack.count = 0;
+                count
+            }
+        };
+
+        self.set(from, Some(stack));
+        moved
+    }
+
+    /// Adds a stack to matching stacks first and then to empty slots, hotbar before the main grid.
+    /// The off-hand only ever gets topped up. Returns whatever did not fit
+    pub fn insert(&mut self, mut stack: ItemStack) -> Option<ItemStack> {
+        let slots: Vec<Slot> = self.slots().collect();
+
+        for slot in slots.iter().copied() {
+            if let Some(target) = self.get_mut(slot) {
+                target.merge(&mut stack);
+            }
+            if stack.is_empty() {
+                return None;
+            }
+        }
+
+        for slot in slots {
+            if slot != Slot::OffHand && self.get(slot).is_none() {
+                let count = stack.count.min(stack.max_stack_size);
+                self.set(slot, stack.split(count));
+            }
+            if stack.is_empty() {
+                return None;
+            }
+        }
+
+        Some(stack)
+    }
+
+    /// Every slot, hotbar first, then the off-hand and the main grid
+    pub fn slots(&self) -> impl Iterator<Item = Slot> {
+        (0..HOTBAR_SIZE)
+            .map(Slot::Hotbar)
+            .chain(std::iter::once(Slot::OffHand))
+            .chain((0..MAIN_SIZE).map(Slot::Main))
+    }
+
+    /// Every filled slot with its stack
+    pub fn stacks(&self) -> impl Iterator<Item = (Slot, &ItemStack)> + '_ {
+        self.slots().filter_map(|slot| Some((slot, self.get(slot)?)))
+    }
+}
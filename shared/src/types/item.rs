@@ -1,8 +1,10 @@
-pub trait Item {
-    type Args;
+use serde::Deserialize;
+
+use super::{context::Context, Key, KeyError};
 
-    fn on_use(&mut self, args: Self::Args, target: Target, hand: Hand);
-    fn on_update(&mut self, args: Self::Args, location: Location);
+pub trait Item {
+    fn on_use(&self, ctx: &mut Context, target: Target, hand: Hand);
+    fn on_update(&self, ctx: &mut Context, location: Location);
 }
 
 pub enum Target {
@@ -11,13 +13,39 @@ pub enum Target {
     Entity,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Hand {
     MainHand,
     OffHand
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Location {
     Inventory,
     Hotbar,
     Hand(Hand),
-}
\ No newline at end of file
+}
+
+/// An item described by an addon's `items/<path>.toml`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemDefinition {
+    pub display_name: String,
+    pub texture: Option<String>,
+    #[serde(default = "default_max_stack_size")]
+    pub max_stack_size: u16,
+}
+
+impl ItemDefinition {
+    /// Prefixes a texture name that has no namespace with `namespace`
+    pub fn qualify(&mut self, namespace: &str) -> Result<(), KeyError> {
+        if let Some(texture) = &mut self.texture {
+            *texture = Key::parse_in(namespace, texture)?.to_string();
+        }
+        Ok(())
+    }
+}
+
+fn default_max_stack_size() -> u16 {
+    64
+}
@@ -0,0 +1,241 @@
+use std::{collections::HashSet, fmt};
+
+use serde::Deserialize;
+
+use super::{inventory::ItemStack, recipe::Ingredient, Key, KeyError};
+
+/// The most entries a pool can pick, so an addon can't stall a tick by rolling billions of times
+pub const MAX_ROLLS: u32 = 256;
+
+/// What drops from a block or anything else that rolls loot. Every pool is rolled on its own
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LootTable {
+    pub pools: Vec<LootPool>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LootPool {
+    /// How many entries are picked
+    pub rolls: LootRange,
+    /// Every one has to pass for the pool to roll at all
+    pub conditions: Vec<LootCondition>,
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LootEntry {
+    pub kind: LootEntryKind,
+    /// Relative to the other entries of the pool that pass their conditions
+    pub weight: u32,
+    pub count: LootRange,
+    pub conditions: Vec<LootCondition>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LootEntryKind {
+    Item(Key),
+    /// One random item from an item tag
+    Tag(Key),
+    /// Nothing, to make the other entries less likely
+    Empty,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LootCondition {
+    /// The tool used, such as `#cavern:pickaxes`
+    Tool(Ingredient),
+    /// Something about how the loot is rolled, such as `silk_touch`
+    Flag(String),
+    NoFlag(String),
+    /// Passes with a probability from `0` to `1`
+    Chance(f32),
+}
+
+/// Inclusive on both ends
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LootRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl LootRange {
+    pub fn exactly(count: u32) -> Self {
+        Self { min: count, max: count }
+    }
+}
+
+/// How loot is being rolled, what conditions are checked against
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LootContext {
+    pub tool: Option<ItemStack>,
+    pub flags: HashSet<String>,
+}
+
+impl LootContext {
+    pub fn with_tool(mut self, tool: ItemStack) -> Self {
+        self.tool = Some(tool);
+        self
+    }
+
+    pub fn with_flag(mut self, flag: impl Into<String>) -> Self {
+        self.flags.insert(flag.into());
+        self
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LootDrop {
+    pub item: Key,
+    pub count: u32,
+}
+
+/// A loot table described by an addon's `loot_tables/<path>.toml`. Blocks drop the table sharing their key
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LootTableDefinition {
+    #[serde(default)]
+    pub pools: Vec<LootPoolDefinition>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LootPoolDefinition {
+    #[serde(default = "default_range")]
+    pub rolls: LootRangeDefinition,
+    #[serde(default)]
+    pub conditions: Vec<LootConditionDefinition>,
+    pub entries: Vec<LootEntryDefinition>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LootEntryDefinition {
+    /// An item, a `#tag` or nothing at all
+    pub item: Option<String>,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default = "default_range")]
+    pub count: LootRangeDefinition,
+    #[serde(default)]
+    pub conditions: Vec<LootConditionDefinition>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LootConditionDefinition {
+    Tool(String),
+    Flag(String),
+    NoFlag(String),
+    Chance(f32),
+}
+
+/// Either an exact number or `{ min = .., max = .. }`
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum LootRangeDefinition {
+    Exactly(u32),
+    Range { min: u32, max: u32 },
+}
+
+fn default_range() -> LootRangeDefinition {
+    LootRangeDefinition::Exactly(1)
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl LootTableDefinition {
+    /// Keys without a namespace fall back to `namespace`
+    pub fn into_loot_table(self, namespace: &str) -> Result<LootTable, LootError> {
+        let pools = self
+            .pools
+            .into_iter()
+            .map(|pool| {
+                let rolls = LootRange::from(pool.rolls);
+                if rolls.max > MAX_ROLLS {
+                    return Err(LootError::Rolls(rolls.max));
+                }
+                let total: u64 = pool.entries.iter().map(|entry| u64::from(entry.weight)).sum();
+                if total > u64::from(u32::MAX) {
+                    return Err(LootError::Weight(total));
+                }
+
+                Ok(LootPool {
+                    rolls,
+                    conditions: conditions(pool.conditions, namespace)?,
+                    entries: pool
+                        .entries
+                        .into_iter()
+                        .map(|entry| {
+                            let kind = match entry.item.as_deref().map(|item| Ingredient::parse_in(namespace, item)) {
+                                Some(Ok(Ingredient::Item(item))) => LootEntryKind::Item(item),
+                                Some(Ok(Ingredient::Tag(tag))) => LootEntryKind::Tag(tag),
+                                Some(Err(e)) => return Err(e),
+                                None => LootEntryKind::Empty,
+                            };
+                            Ok(LootEntry {
+                                kind,
+                                weight: entry.weight,
+                                count: entry.count.into(),
+                                conditions: conditions(entry.conditions, namespace)?,
+                            })
+                        })
+                        .collect::<Result<_, KeyError>>()?,
+                })
+            })
+            .collect::<Result<_, LootError>>()?;
+
+        Ok(LootTable { pools })
+    }
+}
+
+fn conditions(definitions: Vec<LootConditionDefinition>, namespace: &str) -> Result<Vec<LootCondition>, KeyError> {
+    definitions
+        .into_iter()
+        .map(|condition| {
+            Ok(match condition {
+                LootConditionDefinition::Tool(tool) => LootCondition::Tool(Ingredient::parse_in(namespace, &tool)?),
+                LootConditionDefinition::Flag(flag) => LootCondition::Flag(flag),
+                LootConditionDefinition::NoFlag(flag) => LootCondition::NoFlag(flag),
+                LootConditionDefinition::Chance(chance) => LootCondition::Chance(chance),
+            })
+        })
+        .collect()
+}
+
+impl From<LootRangeDefinition> for LootRange {
+    fn from(range: LootRangeDefinition) -> Self {
+        match range {
+            LootRangeDefinition::Exactly(count) => LootRange::exactly(count),
+            LootRangeDefinition::Range { min, max } => LootRange { min, max: max.max(min) },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LootError {
+    Key(KeyError),
+    /// A pool rolls more than [`MAX_ROLLS`] times
+    Rolls(u32),
+    /// The weights of a pool add up to more than fits in a `u32`
+    Weight(u64),
+}
+
+impl From<KeyError> for LootError {
+    fn from(e: KeyError) -> Self {
+        LootError::Key(e)
+    }
+}
+
+impl fmt::Display for LootError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LootError::Key(e) => e.fmt(f),
+            LootError::Rolls(rolls) => write!(f, "Pool rolls up to {rolls} times, at most {MAX_ROLLS} are allowed"),
+            LootError::Weight(total) => write!(f, "Pool weights add up to {total}, at most {} are allowed", u32::MAX),
+        }
+    }
+}
+
+impl std::error::Error for LootError {}
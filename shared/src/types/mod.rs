@@ -1,37 +1,168 @@
-use std::hash::Hash;
+use std::{fmt, hash::Hash, str::FromStr};
 
-use self::{item::Item, block::Block};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use self::{item::Item, block::Block, loot::LootTable, recipe::Recipe};
 
 pub mod block;
+pub mod block_entity;
+pub mod context;
+pub mod inventory;
 pub mod item;
+pub mod loot;
+pub mod recipe;
+pub mod state;
 
-#[derive(Debug, Eq, Hash, PartialEq)]
-pub struct Id<T: Eq + Hash + PartialEq> {
+/// A human readable `namespace:path` key
+///
+/// Namespaces may contain `a-z`, `0-9`, `_`, `-` and `.`, paths may additionally contain `/`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Key {
     pub namespace: String,
-    pub id: u32,
+    pub path: String,
+}
+
+impl Key {
+    pub fn new(namespace: impl Into<String>, path: impl Into<String>) -> Result<Self, KeyError> {
+        let namespace = namespace.into();
+        let path = path.into();
+
+        if namespace.is_empty() || !namespace.chars().all(valid_namespace_char) {
+            return Err(KeyError::InvalidNamespace(namespace));
+        }
+        if path.is_empty() || !path.chars().all(valid_path_char) {
+            return Err(KeyError::InvalidPath(path));
+        }
+
+        Ok(Self { namespace, path })
+    }
+
+    #[profiling::function]
+    pub fn parse(key: &str) -> Result<Self, KeyError> {
+        let Some((namespace, path)) = key.split_once(':') else {
+            return Err(KeyError::MissingSeparator(key.to_string()));
+        };
+        Self::new(namespace, path)
+    }
+
+    /// Like `parse`, but keys without a namespace fall back to `namespace`
+    pub fn parse_in(namespace: &str, key: &str) -> Result<Self, KeyError> {
+        if key.contains(':') {
+            Self::parse(key)
+        } else {
+            Self::new(namespace, key)
+        }
+    }
+}
+
+fn valid_namespace_char(c: char) -> bool {
+    matches!(c, 'a'..='z' | '0'..='9' | '_' | '-' | '.')
+}
+
+fn valid_path_char(c: char) -> bool {
+    valid_namespace_char(c) || c == '/'
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
+
+impl FromStr for Key {
+    type Err = KeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<&str> for Key {
+    type Error = KeyError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Self::parse(&key).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KeyError {
+    MissingSeparator(String),
+    InvalidNamespace(String),
+    InvalidPath(String),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::MissingSeparator(key) => write!(f, "Key \"{key}\" is missing a \"namespace:\" prefix"),
+            KeyError::InvalidNamespace(namespace) => write!(f, "Namespace \"{namespace}\" must be non-empty and only contain [a-z0-9_.-]"),
+            KeyError::InvalidPath(path) => write!(f, "Path \"{path}\" must be non-empty and only contain [a-z0-9_.-/]"),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+/// A key within a registry category. The numeric index is assigned by the registry at runtime
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Id<T: Eq + Hash + PartialEq> {
+    pub key: Key,
     pub r#type: Type<T>,
 }
 
 impl<T: Eq + Hash + PartialEq> Id<T> {
-    pub fn new(r#type: Type<T>, namespace: String, id: u32) -> Self {
+    pub fn new(r#type: Type<T>, key: Key) -> Self {
         Self {
-            namespace,
-            id,
+            key,
             r#type,
         }
     }
+
+    pub fn parse(r#type: Type<T>, key: &str) -> Result<Self, KeyError> {
+        Ok(Self::new(r#type, Key::parse(key)?))
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.key.namespace
+    }
+
+    pub fn path(&self) -> &str {
+        &self.key.path
+    }
+}
+
+impl<T: Eq + Hash + PartialEq> fmt::Display for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.key.fmt(f)
+    }
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum Type<CustomType> {
     Builtin(BuiltinType),
     Custom(CustomType),
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum BuiltinType {
     Block,
     Item,
+    Recipe,
+    LootTable,
 }
 
 pub enum Data<B: Block, I: Item, CustomData> {
@@ -42,4 +173,6 @@ pub enum Data<B: Block, I: Item, CustomData> {
 pub enum BuiltinData<B: Block, I: Item> {
     Block(B),
     Item(I),
-}
\ No newline at end of file
+    Recipe(Recipe),
+    LootTable(LootTable),
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Deserialize;
+
+use super::{Key, KeyError};
+
+/// Something a recipe slot accepts, either one item or any item in a tag
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Ingredient {
+    Item(Key),
+    Tag(Key),
+}
+
+impl Ingredient {
+    /// Tags are written with a leading `#`, keys without a namespace fall back to `namespace`
+    pub fn parse_in(namespace: &str, value: &str) -> Result<Self, KeyError> {
+        match value.strip_prefix('#') {
+            Some(tag) => Ok(Ingredient::Tag(Key::parse_in(namespace, tag)?)),
+            None => Ok(Ingredient::Item(Key::parse_in(namespace, value)?)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecipeOutput {
+    pub item: Key,
+    pub count: u16,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recipe {
+    /// Ingredients in rows, `None` where the grid has to stay empty. Fits anywhere in a larger grid, mirrored or not
+    Shaped {
+        width: usize,
+        height: usize,
+        ingredients: Vec<Option<Ingredient>>,
+        output: RecipeOutput,
+    },
+    /// Ingredients in any arrangement
+    Shapeless {
+        ingredients: Vec<Ingredient>,
+        output: RecipeOutput,
+    },
+    /// One input turned into an output over `time` ticks by whatever handles `kind`, such as `cavern:smelting`
+    Processing {
+        kind: Key,
+        input: Ingredient,
+        output: RecipeOutput,
+        time: u32,
+    },
+    /// A recipe type added by an addon, left for whatever handles `kind` to interpret
+    Custom {
+        kind: Key,
+        data: toml::Table,
+    },
+}
+
+impl Recipe {
+    /// Reads a recipe from an addon's `recipes/<path>.toml`. The `type` field picks between `shaped`, `shapeless`,
+    /// `processing` and custom types, which are keys. Keys without a namespace fall back to `namespace`
+    pub fn from_table(mut table: toml::Table, namespace: &str) -> Result<Self, RecipeError> {
+        let kind = match table.remove("type") {
+            Some(toml::Value::String(kind)) => kind,
+            _ => return Err(RecipeError::MissingType),
+        };
+
+        match kind.as_str() {
+            "shaped" => table.try_into::<ShapedDefinition>()?.into_recipe(namespace),
+            "shapeless" => {
+                let definition: ShapelessDefinition = table.try_into()?;
+                Ok(Recipe::Shapeless {
+                    ingredients: definition
+                        .ingredients
+                        .iter()
+                        .map(|ingredient| Ingredient::parse_in(namespace, ingredient))
+                        .collect::<Result<_, _>>()?,
+                    output: definition.result.into_output(namespace)?,
+                })
+            }
+            "processing" => {
+                let definition: ProcessingDefinition = table.try_into()?;
+                Ok(Recipe::Processing {
+                    kind: Key::parse_in(namespace, &definition.kind)?,
+                    input: Ingredient::parse_in(namespace, &definition.input)?,
+                    output: definition.result.into_output(namespace)?,
+                    time: definition.time,
+                })
+            }
+            kind => Ok(Recipe::Custom {
+                kind: Key::parse_in(namespace, kind)?,
+                data: table,
+            }),
+        }
+    }
+
+    pub fn output(&self) -> Option<&RecipeOutput> {
+        match self {
+            Recipe::Shaped { output, .. } | Recipe::Shapeless { output, .. } | Recipe::Processing { output, .. } => {
+                Some(output)
+            }
+            Recipe::Custom { .. } => None,
+        }
+    }
+
+    /// Every ingredient the recipe uses, with repeats
+    pub fn ingredients(&self) -> Vec<&Ingredient> {
+        match self {
+            Recipe::Shaped { ingredients, .. } => ingredients.iter().flatten().collect(),
+            Recipe::Shapeless { ingredients, .. } => ingredients.iter().collect(),
+            Recipe::Processing { input, .. } => vec![input],
+            Recipe::Custom { .. } => vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShapedDefinition {
+    /// Rows of single character keys, spaces stay empty
+    pattern: Vec<String>,
+    key: HashMap<char, String>,
+    result: OutputDefinition,
+}
+
+impl ShapedDefinition {
+    fn into_recipe(self, namespace: &str) -> Result<Recipe, RecipeError> {
+        let rows: Vec<Vec<char>> = self.pattern.iter().map(|row| row.chars().collect()).collect();
+        let filled = |x: usize, y: usize| rows[y].get(x).is_some_and(|c| *c != ' ');
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let used_rows: Vec<usize> = (0..rows.len()).filter(|y| (0..columns).any(|x| filled(x, *y))).collect();
+        let used_columns: Vec<usize> = (0..columns).filter(|x| (0..rows.len()).any(|y| filled(*x, y))).collect();
+        let (Some(top), Some(bottom)) = (used_rows.first(), used_rows.last()) else {
+            return Err(RecipeError::Pattern("Pattern is empty".to_string()));
+        };
+        let (Some(left), Some(right)) = (used_columns.first(), used_columns.last()) else {
+            return Err(RecipeError::Pattern("Pattern is empty".to_string()));
+        };
+
+        let mut ingredients = vec![];
+        for row in &rows[*top..=*bottom] {
+            for x in *left..=*right {
+                let c = row.get(x).copied().unwrap_or(' ');
+                if c == ' ' {
+                    ingredients.push(None);
+                    continue;
+                }
+                let Some(ingredient) = self.key.get(&c) else {
+                    return Err(RecipeError::Pattern(format!("Pattern uses '{c}', which is not in the key")));
+                };
+                ingredients.push(Some(Ingredient::parse_in(namespace, ingredient)?));
+            }
+        }
+
+        Ok(Recipe::Shaped {
+            width: right - left + 1,
+            height: bottom - top + 1,
+            ingredients,
+            output: self.result.into_output(namespace)?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShapelessDefinition {
+    ingredients: Vec<String>,
+    result: OutputDefinition,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProcessingDefinition {
+    kind: String,
+    input: String,
+    #[serde(default = "default_processing_time")]
+    time: u32,
+    result: OutputDefinition,
+}
+
+fn default_processing_time() -> u32 {
+    200
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutputDefinition {
+    item: String,
+    #[serde(default = "default_count")]
+    count: u16,
+}
+
+impl OutputDefinition {
+    fn into_output(self, namespace: &str) -> Result<RecipeOutput, KeyError> {
+        Ok(RecipeOutput {
+            item: Key::parse_in(namespace, &self.item)?,
+            count: self.count,
+        })
+    }
+}
+
+fn default_count() -> u16 {
+    1
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecipeError {
+    MissingType,
+    Key(KeyError),
+    Toml(toml::de::Error),
+    Pattern(String),
+}
+
+impl From<KeyError> for RecipeError {
+    fn from(e: KeyError) -> Self {
+        RecipeError::Key(e)
+    }
+}
+
+impl From<toml::de::Error> for RecipeError {
+    fn from(e: toml::de::Error) -> Self {
+        RecipeError::Toml(e)
+    }
+}
+
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecipeError::MissingType => write!(f, "Recipe is missing a \"type\""),
+            RecipeError::Key(e) => e.fmt(f),
+            RecipeError::Toml(e) => write!(f, "{}", e.message()),
+            RecipeError::Pattern(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for RecipeError {}
@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::Key;
+
+/// A compact id for one state of one block, what chunks store per position.
+/// `StateId::AIR` is reserved for empty space, every registered block state comes after it
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StateId(pub u32);
+
+impl StateId {
+    pub const AIR: StateId = StateId(0);
+}
+
+/// A state written out by block key and property values, which keeps its meaning when blocks are added to or
+/// removed from the registry
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct StateKey {
+    pub block: Key,
+    /// Property values as text, keyed by property name
+    pub properties: BTreeMap<String, String>,
+}
+
+/// A block's registry index together with its local state index
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BlockState {
+    pub block: u32,
+    pub state: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub kind: PropertyKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyKind {
+    Bool,
+    /// Inclusive on both ends
+    Int { min: i32, max: i32 },
+    Enum(Vec<String>),
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i32),
+    Enum(String),
+}
+
+impl Value {
+    pub fn enumeration(value: impl Into<String>) -> Self {
+        Value::Enum(value.into())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(value) => value.fmt(f),
+            Value::Int(value) => value.fmt(f),
+            Value::Enum(value) => value.fmt(f),
+        }
+    }
+}
+
+impl Property {
+    /// The most values a single property may take
+    pub const MAX_VALUES: u32 = 1 << 16;
+
+    pub fn bool(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind: PropertyKind::Bool,
+        }
+    }
+
+    /// Fails if `min` is above `max` or the range has more than `Property::MAX_VALUES` values
+    pub fn int(name: impl Into<String>, min: i32, max: i32) -> Result<Self, StateError> {
+        let property = Self {
+            name: name.into(),
+            kind: PropertyKind::Int { min, max },
+        };
+        property.checked_count()?;
+        Ok(property)
+    }
+
+    pub fn enumeration(name: impl Into<String>, values: &[&str]) -> Self {
+        Self {
+            name: name.into(),
+            kind: PropertyKind::Enum(values.iter().map(|value| value.to_string()).collect()),
+        }
+    }
+
+    /// Number of values this property can take
+    pub fn count(&self) -> u32 {
+        match &self.kind {
+            PropertyKind::Bool => 2,
+            PropertyKind::Int { min, max } => max.abs_diff(*min).saturating_add(1),
+            PropertyKind::Enum(values) => u32::try_from(values.len().max(1)).unwrap_or(u32::MAX),
+        }
+    }
+
+    /// Like `count`, but fails for an inverted int range or more than `Property::MAX_VALUES` values
+    pub fn checked_count(&self) -> Result<u32, StateError> {
+        if let PropertyKind::Int { min, max } = self.kind {
+            if min > max {
+                return Err(StateError::InvalidRange {
+                    name: self.name.clone(),
+                    min,
+                    max,
+                });
+            }
+        }
+
+        let count = match &self.kind {
+            PropertyKind::Bool => 2,
+            PropertyKind::Int { min, max } => u64::from(max.abs_diff(*min)) + 1,
+            PropertyKind::Enum(values) => values.len().max(1) as u64,
+        };
+        if count > u64::from(Self::MAX_VALUES) {
+            return Err(StateError::TooManyValues {
+                name: self.name.clone(),
+                count,
+            });
+        }
+        Ok(count as u32)
+    }
+
+    pub fn value(&self, index: u32) -> Option<Value> {
+        if index >= self.count() {
+            return None;
+        }
+
+        match &self.kind {
+            PropertyKind::Bool => Some(Value::Bool(index == 1)),
+            PropertyKind::Int { min, .. } => Some(Value::Int(min.wrapping_add_unsigned(index))),
+            PropertyKind::Enum(values) => values.get(index as usize).cloned().map(Value::Enum),
+        }
+    }
+
+    /// Reads a value written by `Value`'s `Display`, `None` if this property can't take it
+    pub fn parse(&self, text: &str) -> Option<Value> {
+        let value = match &self.kind {
+            PropertyKind::Bool => Value::Bool(text.parse().ok()?),
+            PropertyKind::Int { .. } => Value::Int(text.parse().ok()?),
+            PropertyKind::Enum(_) => Value::enumeration(text),
+        };
+        self.index(&value)?;
+        Some(value)
+    }
+
+    pub fn index(&self, value: &Value) -> Option<u32> {
+        match (&self.kind, value) {
+            (PropertyKind::Bool, Value::Bool(value)) => Some(u32::from(*value)),
+            (PropertyKind::Int { min, max }, Value::Int(value)) if (min..=max).contains(&value) => {
+                Some(value.abs_diff(*min))
+            }
+            (PropertyKind::Enum(values), Value::Enum(value)) => {
+                values.iter().position(|other| other == value).map(|index| index as u32)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The properties a block declares. Every combination of their values is one local state index,
+/// with the first value of every property being state `0`
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateDefinition {
+    properties: Vec<Property>,
+    count: u32,
+}
+
+impl Default for StateDefinition {
+    fn default() -> Self {
+        Self {
+            properties: vec![],
+            count: 1,
+        }
+    }
+}
+
+impl StateDefinition {
+    /// Fails if a property is invalid or the combinations of all values don't fit into a `u32`
+    pub fn new(properties: Vec<Property>) -> Result<Self, StateError> {
+        let mut count: u32 = 1;
+        for property in &properties {
+            count = count
+                .checked_mul(property.checked_count()?)
+                .ok_or(StateError::TooManyStates)?;
+        }
+        Ok(Self { properties, count })
+    }
+
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
+
+    /// Number of local states
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// The position of a property and the local state stride of its values
+    fn find(&self, name: &str) -> Option<(&Property, u32)> {
+        let mut stride = 1;
+        for property in &self.properties {
+            if property.name == name {
+                return Some((property, stride));
+            }
+            stride *= property.count();
+        }
+        None
+    }
+
+    pub fn get(&self, state: u32, name: &str) -> Option<Value> {
+        let (property, stride) = self.find(name)?;
+        property.value(state / stride % property.count())
+    }
+
+    /// The local state with one property changed, or `None` if the property or value does not exist
+    pub fn set(&self, state: u32, name: &str, value: &Value) -> Option<u32> {
+        let (property, stride) = self.find(name)?;
+        let index = property.index(value)?;
+        let current = state / stride % property.count();
+        Some(state - current * stride + index * stride)
+    }
+
+    /// The local state with one property moved to its next value, wrapping around
+    pub fn cycle(&self, state: u32, name: &str) -> Option<u32> {
+        let (property, stride) = self.find(name)?;
+        let current = state / stride % property.count();
+        let next = (current + 1) % property.count();
+        Some(state - current * stride + next * stride)
+    }
+
+    /// Every property name with its value in a local state
+    pub fn values(&self, state: u32) -> Vec<(&str, Value)> {
+        let mut stride = 1;
+        let mut values = Vec::with_capacity(self.properties.len());
+        for property in &self.properties {
+            if let Some(value) = property.value(state / stride % property.count()) {
+                values.push((property.name.as_str(), value));
+            }
+            stride *= property.count();
+        }
+        values
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StateError {
+    InvalidRange { name: String, min: i32, max: i32 },
+    TooManyValues { name: String, count: u64 },
+    /// A block, or all blocks together, have more states than a `StateId` can address
+    TooManyStates,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidRange { name, min, max } => {
+                write!(f, "Property \"{name}\" has a minimum of {min} above its maximum of {max}")
+            }
+            StateError::TooManyValues { name, count } => write!(
+                f,
+                "Property \"{name}\" has {count} values, at most {} are allowed",
+                Property::MAX_VALUES
+            ),
+            StateError::TooManyStates => write!(f, "Too many block states to fit into a state id"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
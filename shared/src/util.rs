@@ -8,8 +8,81 @@ pub fn if_let<F, O, A>(option: Option<O>, args: A, if_some: F) where F: FnOnce(O
     if_some(value, args);
 }
 
+/// An axis aligned box from its minimum to its maximum corner
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct BoundingBox(pub Vec3, pub Vec3);
 
+impl BoundingBox {
+    /// One whole block
+    pub const FULL: BoundingBox = BoundingBox(Vec3::ZERO, Vec3::ONE);
+
+    /// Orders the corners, so they can be given either way around
+    pub fn new(a: Vec3, b: Vec3) -> Self {
+        Self(a.min(b), a.max(b))
+    }
+
+    pub fn min(&self) -> Vec3 {
+        self.0
+    }
+
+    pub fn max(&self) -> Vec3 {
+        self.1
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.1 - self.0
+    }
+
+    pub fn offset(&self, by: Vec3) -> Self {
+        Self(self.0 + by, self.1 + by)
+    }
+
+    /// Stretches the box along `by`, covering everything it passes while moving
+    pub fn expand(&self, by: Vec3) -> Self {
+        Self(self.0 + by.min(Vec3::ZERO), self.1 + by.max(Vec3::ZERO))
+    }
+
+    /// Whether the boxes overlap, touching faces do not count
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.0.cmplt(other.1).all() && other.0.cmplt(self.1).all()
+    }
+
+    /// How far along `direction` a ray from `origin` enters the box, with the normal of the face it enters through.
+    /// Distances are in multiples of `direction`, rays starting inside never hit
+    pub fn ray(&self, origin: Vec3, direction: Vec3) -> Option<(f32, Vec3)> {
+        let mut enter = f32::NEG_INFINITY;
+        let mut exit = f32::INFINITY;
+        let mut normal = Vec3::ZERO;
+
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] <= self.0[axis] || origin[axis] >= self.1[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let a = (self.0[axis] - origin[axis]) / direction[axis];
+            let b = (self.1[axis] - origin[axis]) / direction[axis];
+            let (near, far) = (a.min(b), a.max(b));
+            if near > enter {
+                enter = near;
+                normal = Vec3::ZERO;
+                normal[axis] = -direction[axis].signum();
+            }
+            exit = exit.min(far);
+        }
+
+        (enter >= 0.0 && enter < exit).then_some((enter, normal))
+    }
+
+    /// How much of `velocity` the `moving` box can travel before touching this one, with the normal of the face it touches
+    pub fn sweep(&self, moving: &BoundingBox, velocity: Vec3) -> Option<(f32, Vec3)> {
+        let target = BoundingBox(self.0 - moving.size(), self.1);
+        target.ray(moving.0, velocity).filter(|(time, _)| *time <= 1.0)
+    }
+}
+
 pub enum ThisOrThat<I, A> {
     This(I),
     That(A),
@@ -29,4 +102,22 @@ impl<K, V, S> GetOrInsert<K, V> for HashMap<K, V, S> where K: Eq, K: Hash, S: Bu
     fn get_or_insert(&mut self, get: impl Into<K>, insert: V) -> &mut V {
         self.entry(get.into()).or_insert(insert)
     }
-}
\ No newline at end of file
+}
+
+/// Levenshtein distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}